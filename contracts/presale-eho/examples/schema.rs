@@ -5,7 +5,8 @@ use cosmwasm_schema::{export_schema_with_title, remove_schemas, schema_for};
 use cosmwasm_std::Uint128;
 
 // Import your contract's message and state types
-use presale_eho::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use presale_eho::msg::{ExecuteMsg, InstantiateMsg, IsWhitelistedResponse, QueryMsg};
+use presale_eho::oracle::{OracleQueryMsg, PriceResponse};
 use presale_eho::state::{Config, State};
 
 fn main() {
@@ -23,6 +24,14 @@ fn main() {
     // Note: We use schema_for!(<ResponseType>) here
     export_schema_with_title(&schema_for!(Config), &out_dir, "ConfigResponse");
     export_schema_with_title(&schema_for!(State), &out_dir, "StateResponse");
-    export_schema_with_title(&schema_for!(bool), &out_dir, "IsWhitelistedResponse");
+    export_schema_with_title(
+        &schema_for!(IsWhitelistedResponse),
+        &out_dir,
+        "IsWhitelistedResponse",
+    );
     export_schema_with_title(&schema_for!(Uint128), &out_dir, "ContributionOfResponse");
-}
\ No newline at end of file
+
+    // The interface expected from the price oracle
+    export_schema_with_title(&schema_for!(OracleQueryMsg), &out_dir, "OracleQueryMsg");
+    export_schema_with_title(&schema_for!(PriceResponse), &out_dir, "OraclePriceResponse");
+}
@@ -1,83 +1,255 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult, Uint128, WasmMsg,
+    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdResult, Storage, Uint128, WasmMsg,
 };
 use cw2::{ensure_from_older_version, set_contract_version};
-use cw20::Cw20ExecuteMsg;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_controllers::AdminError;
+use cw_storage_plus::{Bound, Item};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, Rate};
+use crate::msg::{
+    AcceptedRateResponse, AllContributionsResponse, AllWhitelistedResponse, AllocationResponse,
+    ClaimableResponse, ConfigChangesResponse, ConfigUpdate, ContributorInfo, ExecuteMsg,
+    FundingResponse, InstantiateMsg, IsWhitelistedResponse, KycVoucher, MigrateMsg, QueryMsg, Rate,
+    ReceiveMsg, ReferralStatsResponse, ReferrerInfo, RoleMember, RolesResponse, RoundResponse,
+    SaleStatsResponse, WhitelistAddition, WhitelistProof,
+};
+use crate::oracle::resolve_rate;
 use crate::state::{
-    Config, SaleStatus, State, CONFIG, CONTRIBUTIONS, EXCHANGE_RATES, STATE, WHITELIST,
+    AccessMode, Asset, AssetInfo, AuctionBid, Config, ConfigChange, Contribution, Distribution,
+    OracleConfig, ParamChange, PendingAdmin, Role, SaleMode, SaleStatus, State, VestingSchedule,
+    WhitelistEntry, ADMIN, AUCTION_BIDS, AUCTION_BID_TOTALS, CLAIMED, CONFIG, CONFIG_CHANGES,
+    CONTRIBUTIONS, DEFAULT_PAYMENT_DECIMALS, DENOM_CAPS, DISTRIBUTION_CURSOR, EXCESS_REFUNDED,
+    EXCESS_REFUNDED_TOTALS, EXCHANGE_RATES, KYC_SIGNER, MAX_END_TIME_EXTENSION,
+    MAX_PAYMENT_DECIMALS, MERKLE_ROOT, PAYMENT_DECIMALS, PENDING_ADMIN, RAISED_VALUE_BY_DENOM,
+    REFERRAL_BONUSES, REFERRAL_BONUS_TOTAL, REFERRER_STATS, REFUND_CURSOR, ROLES,
+    ROUND_CONTRIBUTIONS, ROUND_TOTALS, STATE, TIER_BONUSES, TIER_BONUS_RESERVED,
+    TOTAL_RAISED_BY_ASSET, USED_VOUCHER_NONCES, WHITELIST,
 };
+use crate::whitelist::{leaf_hash, parse_merkle_root, verify_merkle_proof, voucher_hash};
 
 const CONTRACT_NAME: &str = "crates.io:eho-presale-multi-asset";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+// The most contributors settled by one `DistributeBatch` or `RefundBatch`
+const MAX_BATCH_SIZE: u32 = 50;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // Validate parameters
-    if msg.start_time >= msg.end_time {
-        return Err(ContractError::ConfigError {
-            details: "Start time must be before end time".to_string(),
-        });
-    }
-    if msg.soft_cap > msg.hard_cap {
-        return Err(ContractError::ConfigError {
-            details: "Soft cap cannot be greater than hard cap".to_string(),
-        });
-    }
-    if msg.hard_cap.is_zero() || msg.max_contribution_per_user.is_zero() || msg.eho_price.is_zero()
-    {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
     if msg.accepted_rates.is_empty() {
         return Err(ContractError::ConfigError {
             details: "At least one accepted rate must be provided".to_string(),
         });
     }
 
+    let oracle = msg
+        .oracle
+        .map(|oracle| validate_oracle(deps.as_ref(), oracle))
+        .transpose()?;
+
     let mut accepted_denoms = vec![];
     for rate in msg.accepted_rates {
-        if rate.rate.is_zero() {
-            return Err(ContractError::InvalidZeroAmount {});
-        }
-        EXCHANGE_RATES.save(deps.storage, &rate.denom, &rate.rate)?;
+        save_rate(deps.storage, &rate.denom, &rate)?;
         accepted_denoms.push(rate.denom);
     }
+    let mut accepted_cw20_tokens = vec![];
+    for rate in msg.accepted_cw20_rates {
+        let token = deps.api.addr_validate(&rate.denom)?;
+        save_rate(deps.storage, token.as_str(), &rate)?;
+        accepted_cw20_tokens.push(token);
+    }
 
+    let admin = deps.api.addr_validate(&msg.admin)?;
+    let treasury = match msg.treasury {
+        Some(treasury) => deps.api.addr_validate(&treasury)?,
+        None => admin.clone(),
+    };
     let config = Config {
-        admin: deps.api.addr_validate(&msg.admin)?,
+        treasury,
         eho_token_address: deps.api.addr_validate(&msg.eho_token_address)?,
         accepted_payment_denoms: accepted_denoms,
+        accepted_cw20_tokens,
         start_time: msg.start_time,
         end_time: msg.end_time,
         soft_cap: msg.soft_cap,
         hard_cap: msg.hard_cap,
         max_contribution_per_user: msg.max_contribution_per_user,
         eho_price: msg.eho_price,
+        rounds: msg.rounds,
+        sale_mode: msg.sale_mode,
+        vesting: msg.vesting,
+        oracle,
+        referral: msg.referral,
+        tier_bonus: msg.tier_bonus,
+        whitelist_end_time: msg.whitelist_end_time,
+        claim_deadline: msg.claim_deadline,
+        distribution: msg.distribution,
     };
+    validate_config(&config)?;
     CONFIG.save(deps.storage, &config)?;
+    ADMIN.set(deps.branch(), Some(admin))?;
 
     let state = State {
         total_usdc_raised: Uint128::zero(),
         sale_status: SaleStatus::Pending,
         paused: false,
+        access_mode: config.access_mode(env.block.time.seconds()),
+        clearing_price: None,
+        contributor_count: 0,
+        claimed_count: 0,
+        total_eho_claimed: Uint128::zero(),
+        tokens_for_sale: Uint128::zero(),
+        refunded_count: 0,
+        end_time_extended: false,
     };
     STATE.save(deps.storage, &state)?;
 
     Ok(Response::new().add_attribute("action", "instantiate"))
 }
 
+/// Checks the sale parameters for consistency, at instantiation and on every `UpdateConfig`.
+fn validate_config(config: &Config) -> Result<(), ContractError> {
+    if config.start_time >= config.end_time {
+        return Err(ContractError::ConfigError {
+            details: "Start time must be before end time".to_string(),
+        });
+    }
+    if config.soft_cap > config.hard_cap {
+        return Err(ContractError::ConfigError {
+            details: "Soft cap cannot be greater than hard cap".to_string(),
+        });
+    }
+    if config.hard_cap.is_zero()
+        || config.max_contribution_per_user.is_zero()
+        || config.eho_price.is_zero()
+    {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut previous_round_end = config.start_time;
+    for round in &config.rounds {
+        if round.start_time >= round.end_time {
+            return Err(ContractError::ConfigError {
+                details: format!("Round '{}' must start before it ends", round.name),
+            });
+        }
+        if round.start_time < previous_round_end || round.end_time > config.end_time {
+            return Err(ContractError::ConfigError {
+                details: "Rounds must be ordered, non-overlapping and within the sale window"
+                    .to_string(),
+            });
+        }
+        if round.eho_price.is_zero()
+            || round.round_cap.is_zero()
+            || round.max_contribution_per_user.is_zero()
+        {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        if round.round_cap > config.hard_cap {
+            return Err(ContractError::ConfigError {
+                details: format!("Cap of round '{}' cannot exceed the hard cap", round.name),
+            });
+        }
+        previous_round_end = round.end_time;
+    }
+
+    if let Some(vesting) = &config.vesting {
+        if vesting.end_time().is_none() {
+            return Err(ContractError::ConfigError {
+                details: "Vesting cannot end beyond the largest timestamp".to_string(),
+            });
+        }
+        if vesting.tge_unlock > Decimal::one() {
+            return Err(ContractError::ConfigError {
+                details: "TGE unlock cannot exceed 100%".to_string(),
+            });
+        }
+        if vesting.tge_unlock < Decimal::one() && vesting.vesting_seconds == 0 {
+            return Err(ContractError::ConfigError {
+                details: "Vesting duration must be set when less than 100% unlocks at TGE"
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some(referral) = &config.referral {
+        if referral.buyer_bonus > Decimal::one() || referral.referrer_bonus > Decimal::one() {
+            return Err(ContractError::ConfigError {
+                details: "Referral bonuses cannot exceed 100%".to_string(),
+            });
+        }
+    }
+    if let SaleMode::DutchAuction {
+        start_price,
+        floor_price,
+        supply,
+        ..
+    } = &config.sale_mode
+    {
+        if floor_price.is_zero() || supply.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        if start_price <= floor_price {
+            return Err(ContractError::ConfigError {
+                details: "Auction start price must be above the floor price".to_string(),
+            });
+        }
+    }
+    if let Some(whitelist_end_time) = config.whitelist_end_time {
+        if whitelist_end_time < config.start_time || whitelist_end_time > config.end_time {
+            return Err(ContractError::ConfigError {
+                details: "Whitelist end time must be within the sale window".to_string(),
+            });
+        }
+    }
+    if let Some(claim_deadline) = config.claim_deadline {
+        let vesting_end = config
+            .vesting
+            .as_ref()
+            .and_then(VestingSchedule::end_time)
+            .unwrap_or_default();
+        if claim_deadline <= config.end_time || claim_deadline < vesting_end {
+            return Err(ContractError::ConfigError {
+                details: "Claim deadline must be after the sale ends and vesting completes"
+                    .to_string(),
+            });
+        }
+    }
+    if let Some(tier_bonus) = &config.tier_bonus {
+        if tier_bonus.tiers.is_empty() || tier_bonus.pool.is_zero() {
+            return Err(ContractError::ConfigError {
+                details: "Bonus tiers need at least one tier and a non-empty pool".to_string(),
+            });
+        }
+        let increasing = tier_bonus
+            .tiers
+            .windows(2)
+            .all(|pair| pair[1].threshold > pair[0].threshold && pair[1].bonus > pair[0].bonus);
+        let first = &tier_bonus.tiers[0];
+        let last = &tier_bonus.tiers[tier_bonus.tiers.len() - 1];
+        if !increasing || first.threshold.is_zero() || last.bonus > Decimal::one() {
+            return Err(ContractError::ConfigError {
+                details: "Bonus tiers must have increasing thresholds and bonuses of at most 100%"
+                    .to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -86,22 +258,152 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Buy {} => execute_buy(deps, env, info),
+        ExecuteMsg::Buy {
+            whitelist_proof,
+            referrer,
+            kyc_voucher,
+        } => execute_buy(deps, env, info, whitelist_proof, referrer, kyc_voucher),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
         ExecuteMsg::ClaimTokens {} => execute_claim_tokens(deps, env, info),
         ExecuteMsg::RequestRefund {} => execute_request_refund(deps, env, info),
+        ExecuteMsg::DistributeBatch { limit } => execute_distribute_batch(deps, env, limit),
+        ExecuteMsg::RefundBatch { limit } => execute_refund_batch(deps, env, limit),
         ExecuteMsg::EndSale {} => execute_end_sale(deps, env, info),
-        ExecuteMsg::AddToWhitelist { addresses } => execute_add_to_whitelist(deps, info, addresses),
+        ExecuteMsg::CancelSale { reason } => execute_cancel_sale(deps, env, info, reason),
+        ExecuteMsg::AddToWhitelist { entries } => execute_add_to_whitelist(deps, info, entries),
         ExecuteMsg::RemoveFromWhitelist { addresses } => {
             execute_remove_from_whitelist(deps, info, addresses)
         }
+        ExecuteMsg::UpdateMerkleRoot { merkle_root } => {
+            execute_update_merkle_root(deps, info, merkle_root)
+        }
+        ExecuteMsg::UpdateKycSigner { pubkey } => execute_update_kyc_signer(deps, info, pubkey),
         ExecuteMsg::ReclaimUnsoldTokens {} => execute_reclaim_unsold_tokens(deps, env, info),
         ExecuteMsg::WithdrawFunds {} => execute_withdraw_funds(deps, env, info),
-        ExecuteMsg::UpdateAdmin { new_admin } => execute_update_admin(deps, info, new_admin),
+        ExecuteMsg::SweepUnclaimed {} => execute_sweep_unclaimed(deps, env, info),
+        ExecuteMsg::ProposeAdmin { new_admin, expiry } => {
+            execute_propose_admin(deps, env, info, new_admin, expiry)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::CancelAdminProposal {} => execute_cancel_admin_proposal(deps, info),
+        ExecuteMsg::RenounceAdmin {} => execute_renounce_admin(deps, info),
+        ExecuteMsg::GrantRole { address, role } => execute_grant_role(deps, info, address, role),
+        ExecuteMsg::RevokeRole { address, role } => execute_revoke_role(deps, info, address, role),
         ExecuteMsg::UpdatePause { pause } => execute_update_pause(deps, info, pause),
+        ExecuteMsg::UpdateRates {
+            rates,
+            cw20_rates,
+            remove,
+            remove_caps,
+        } => execute_update_rates(deps, info, rates, cw20_rates, remove, remove_caps),
+        ExecuteMsg::UpdateOracle { oracle } => execute_update_oracle(deps, info, oracle),
+        ExecuteMsg::UpdateConfig(update) => execute_update_config(deps, env, info, update),
     }
 }
 
-pub fn execute_buy(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn execute_buy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    whitelist_proof: Option<WhitelistProof>,
+    referrer: Option<String>,
+    kyc_voucher: Option<KycVoucher>,
+) -> Result<Response, ContractError> {
+    if info.funds.len() != 1 {
+        return Err(ContractError::InvalidPayment {});
+    }
+    let payment = Asset {
+        info: AssetInfo::Native {
+            denom: info.funds[0].denom.clone(),
+        },
+        amount: info.funds[0].amount,
+    };
+    buy(
+        deps,
+        env,
+        info.sender,
+        payment,
+        whitelist_proof,
+        referrer,
+        kyc_voucher,
+    )
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let msg: ReceiveMsg = from_json(&wrapper.msg)?;
+    if info.sender == config.eho_token_address {
+        if let ReceiveMsg::Fund {} = msg {
+            return execute_fund(deps, wrapper.amount);
+        }
+    }
+    // Only accepted CW20 contracts may pay; anything else is treated like an unknown denom.
+    if !config.accepted_cw20_tokens.contains(&info.sender) {
+        return Err(ContractError::UnacceptedPaymentDenom {
+            denom: info.sender.to_string(),
+        });
+    }
+    let buyer = deps.api.addr_validate(&wrapper.sender)?;
+    let payment = Asset {
+        info: AssetInfo::Cw20 {
+            contract_addr: info.sender,
+        },
+        amount: wrapper.amount,
+    };
+    match msg {
+        ReceiveMsg::Buy {
+            whitelist_proof,
+            referrer,
+            kyc_voucher,
+        } => buy(
+            deps,
+            env,
+            buyer,
+            payment,
+            whitelist_proof,
+            referrer,
+            kyc_voucher,
+        ),
+        ReceiveMsg::Fund {} => Err(ContractError::InvalidPayment {}),
+    }
+}
+
+/// Records EHO sent by the EHO token towards the sale's `tokens_for_sale`.
+fn execute_fund(deps: DepsMut, amount: Uint128) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.distribution == Distribution::Mint {
+        return Err(ContractError::ConfigError {
+            details: "EHO is minted on claim, the sale takes no deposit".to_string(),
+        });
+    }
+    let mut state = STATE.load(deps.storage)?;
+    if !matches!(state.sale_status, SaleStatus::Pending | SaleStatus::Active) {
+        return Err(ContractError::SaleHasEnded {});
+    }
+    state.tokens_for_sale += amount;
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("action", "fund")
+        .add_attribute("amount", amount)
+        .add_attribute("tokens_for_sale", state.tokens_for_sale))
+}
+
+/// Records a purchase of EHO by `buyer` paid with `payment`, which has already been
+/// transferred to the contract (as native funds or through a CW20 `Send`).
+fn buy(
+    mut deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    payment: Asset,
+    whitelist_proof: Option<WhitelistProof>,
+    referrer: Option<String>,
+    kyc_voucher: Option<KycVoucher>,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let mut state = STATE.load(deps.storage)?;
 
@@ -109,6 +411,21 @@ pub fn execute_buy(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respons
         return Err(ContractError::Paused {});
     }
     if state.sale_status == SaleStatus::Pending && env.block.time.seconds() >= config.start_time {
+        if config.distribution == Distribution::Mint {
+            let minter: Option<cw20::MinterResponse> = deps
+                .querier
+                .query_wasm_smart(&config.eho_token_address, &cw20::Cw20QueryMsg::Minter {})?;
+            if minter.is_none_or(|minter| minter.minter != env.contract.address.as_str()) {
+                return Err(ContractError::NotEhoMinter {});
+            }
+        }
+        let required = config.required_eho();
+        if state.tokens_for_sale < required {
+            return Err(ContractError::SaleNotFunded {
+                required,
+                deposited: state.tokens_for_sale,
+            });
+        }
         state.sale_status = SaleStatus::Active;
     }
     if state.sale_status != SaleStatus::Active {
@@ -117,78 +434,357 @@ pub fn execute_buy(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respons
     if env.block.time.seconds() >= config.end_time {
         return Err(ContractError::SaleHasEnded {});
     }
-    if info.funds.len() != 1 {
-        return Err(ContractError::InvalidPayment {});
-    }
-
-    let payment = info.funds[0].clone();
 
     if payment.amount.is_zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let rate = EXCHANGE_RATES
-        .may_load(deps.storage, &payment.denom)?
+    let static_rate = EXCHANGE_RATES
+        .may_load(deps.storage, payment.info.key())?
         .ok_or(ContractError::UnacceptedPaymentDenom {
-            denom: payment.denom.clone(),
+            denom: payment.info.key().to_string(),
         })?;
+    let rate = match &config.oracle {
+        Some(oracle) => resolve_rate(
+            deps.as_ref(),
+            oracle,
+            payment.info.key(),
+            static_rate,
+            env.block.time.seconds(),
+        )?,
+        None => static_rate,
+    };
+    let decimals = payment_decimals(deps.storage, payment.info.key())?;
 
-    let usdc_value = payment.amount.multiply_ratio(rate, Uint128::new(1_000_000));
+    let mut usdc_value = usdc_value_of(payment.amount, rate, decimals);
+    // Dust that is worth nothing once normalised to USDC is not a contribution.
+    if usdc_value.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
 
-    if state.total_usdc_raised + usdc_value > config.hard_cap {
+    // A purchase that would overshoot the hard cap (or the value of a Dutch auction's supply at
+    // the current price) is filled up to the remaining capacity and the unused part of the
+    // payment is returned to the buyer. In `FairLaunch` mode the caps are instead applied
+    // pro-rata at settlement.
+    let now = env.block.time.seconds();
+    let oversubscribable = config.sale_mode == SaleMode::FairLaunch;
+    let sale_capacity = config
+        .auction_capacity(now)
+        .map_or(config.hard_cap, |capacity| capacity.min(config.hard_cap));
+    let remaining_capacity = sale_capacity.saturating_sub(state.total_usdc_raised);
+    let mut accepted = payment.clone();
+    if !oversubscribable && usdc_value > remaining_capacity {
+        accepted.amount = payment
+            .amount
+            .multiply_ratio(remaining_capacity, usdc_value);
+        usdc_value = usdc_value_of(accepted.amount, rate, decimals);
+    }
+    if accepted.amount.is_zero() || usdc_value.is_zero() {
         return Err(ContractError::HardCapReached {});
     }
+    let excess = Asset {
+        info: payment.info.clone(),
+        amount: payment.amount - accepted.amount,
+    };
+
+    let (round_index, round) = config
+        .active_round(now)
+        .ok_or(ContractError::NoActiveRound {})?;
+    let purchase_price = config.auction_price(now).unwrap_or(round.eho_price);
+    let round_total = ROUND_TOTALS
+        .may_load(deps.storage, round_index)?
+        .unwrap_or_default();
+    if !oversubscribable && round_total + usdc_value > round.round_cap {
+        return Err(ContractError::RoundCapReached { round: round.name });
+    }
+    // Exposure to each payment asset is limited regardless of the sale mode.
+    let denom_raised = RAISED_VALUE_BY_DENOM
+        .may_load(deps.storage, payment.info.key())?
+        .unwrap_or_default();
+    if let Some(cap) = DENOM_CAPS.may_load(deps.storage, payment.info.key())? {
+        if denom_raised + usdc_value > cap {
+            return Err(ContractError::DenomCapExceeded {
+                denom: payment.info.key().to_string(),
+                remaining: cap.saturating_sub(denom_raised),
+            });
+        }
+    }
 
-    let user_addr = &info.sender;
-    // if !WHITELIST.load(deps.storage, user_addr).unwrap_or(false) {
-    //     return Err(ContractError::NotInWhitelist {});
-    // }
+    let user_addr = &buyer;
+    let referrer = referrer
+        .map(|referrer| deps.api.addr_validate(&referrer))
+        .transpose()?;
+    if referrer.as_ref() == Some(user_addr) {
+        return Err(ContractError::InvalidReferrer {});
+    }
+    state.access_mode = config.access_mode(now);
+    if let Some(voucher) = kyc_voucher {
+        redeem_kyc_voucher(deps.branch(), &env, user_addr, voucher)?;
+    }
+    if state.access_mode == AccessMode::WhitelistOnly || whitelist_proof.is_some() {
+        ensure_whitelisted(deps.branch(), user_addr, whitelist_proof)?;
+    }
 
+    // Individual whitelist limits take precedence over the sale-wide and per-round caps.
+    let entry = WHITELIST
+        .may_load(deps.storage, user_addr)?
+        .unwrap_or_default();
+    let user_cap = entry.cap.unwrap_or(config.max_contribution_per_user);
     let total_user_usdc_value = get_total_usdc_value(deps.as_ref(), user_addr)?;
-    if total_user_usdc_value + usdc_value > config.max_contribution_per_user {
+    if total_user_usdc_value + usdc_value > user_cap {
+        return Err(ContractError::UserCapExceeded {});
+    }
+    if let Some(min) = entry.min {
+        if total_user_usdc_value + usdc_value < min {
+            return Err(ContractError::BelowMinimumContribution { min });
+        }
+    }
+    let user_round_value = ROUND_CONTRIBUTIONS
+        .may_load(deps.storage, (round_index, user_addr))?
+        .unwrap_or_default();
+    if entry.cap.is_none() && user_round_value + usdc_value > round.max_contribution_per_user {
         return Err(ContractError::UserCapExceeded {});
     }
 
     state.total_usdc_raised += usdc_value;
+    ROUND_TOTALS.save(deps.storage, round_index, &(round_total + usdc_value))?;
+    RAISED_VALUE_BY_DENOM.save(
+        deps.storage,
+        payment.info.key(),
+        &(denom_raised + usdc_value),
+    )?;
+    ROUND_CONTRIBUTIONS.save(
+        deps.storage,
+        (round_index, user_addr),
+        &(user_round_value + usdc_value),
+    )?;
 
+    if !CONTRIBUTIONS.has(deps.storage, user_addr) {
+        state.contributor_count += 1;
+    }
     CONTRIBUTIONS.update(deps.storage, user_addr, |contributions| -> StdResult<_> {
         let mut user_contributions = contributions.unwrap_or_default();
-        if let Some(existing_coin) = user_contributions
+        if let Some(existing) = user_contributions
             .iter_mut()
-            .find(|c| c.denom == payment.denom)
+            .find(|c| c.info == accepted.info)
         {
-            existing_coin.amount += payment.amount;
+            existing.amount += accepted.amount;
+            existing.usdc_value += usdc_value;
         } else {
-            user_contributions.push(payment.clone());
+            user_contributions.push(Contribution {
+                info: accepted.info.clone(),
+                amount: accepted.amount,
+                usdc_value,
+            });
         }
         Ok(user_contributions)
     })?;
+    TOTAL_RAISED_BY_ASSET.update(
+        deps.storage,
+        accepted.info.key(),
+        |raised| -> StdResult<_> {
+            let mut raised = raised.unwrap_or(Asset {
+                info: accepted.info.clone(),
+                amount: Uint128::zero(),
+            });
+            raised.amount += accepted.amount;
+            Ok(raised)
+        },
+    )?;
+
+    // A Dutch auction bid buys EHO at its own price; what it paid above the clearing price is
+    // refunded at settlement. The EHO bought, and so the bonuses earned on it, never depend on
+    // the clearing price.
+    let eho_bought = usdc_value.multiply_ratio(Uint128::new(1_000_000), purchase_price);
+    if config.auction_price(now).is_some() {
+        let bid = AuctionBid {
+            eho: eho_bought,
+            amount_per_price: accepted
+                .amount
+                .multiply_ratio(Uint128::new(1_000_000), purchase_price),
+        };
+        add_auction_bid(deps.storage, user_addr, accepted.info.key(), &bid)?;
+    }
+
+    // Referral bonuses are a share of the EHO bought, fixed at the price of this purchase.
+    let mut response = Response::new();
+    if let Some(referrer) = &referrer {
+        let (buyer_bonus, referrer_bonus) = match &config.referral {
+            Some(referral) => (
+                eho_bought.mul_floor(referral.buyer_bonus),
+                eho_bought.mul_floor(referral.referrer_bonus),
+            ),
+            None => (Uint128::zero(), Uint128::zero()),
+        };
+        add_referral_bonus(deps.storage, user_addr, buyer_bonus)?;
+        add_referral_bonus(deps.storage, referrer, referrer_bonus)?;
+        REFERRER_STATS.update(deps.storage, referrer, |stats| -> StdResult<_> {
+            let mut stats = stats.unwrap_or_default();
+            stats.referred_volume += usdc_value;
+            stats.referred_purchases += 1;
+            stats.bonus_eho += referrer_bonus;
+            Ok(stats)
+        })?;
+        response = response.add_attributes(vec![
+            ("referrer", referrer.to_string()),
+            ("buyer_bonus", buyer_bonus.to_string()),
+            ("referrer_bonus", referrer_bonus.to_string()),
+        ]);
+    }
 
-    if state.total_usdc_raised == config.hard_cap {
+    // Tier bonuses follow the contributor's cumulative value, reserved from the pool as they
+    // are reached so the sale never promises more than the pool holds.
+    if let Some(tier_bonus) = &config.tier_bonus {
+        let contributed = get_total_usdc_value(deps.as_ref(), user_addr)?;
+        let target = get_purchased_eho(deps.as_ref(), &config, user_addr)?
+            .mul_floor(tier_bonus.bonus_for(contributed));
+        let reserved = TIER_BONUSES
+            .may_load(deps.storage, user_addr)?
+            .unwrap_or_default();
+        let total_reserved = TIER_BONUS_RESERVED
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let granted = target
+            .saturating_sub(reserved)
+            .min(tier_bonus.pool.saturating_sub(total_reserved));
+        if !granted.is_zero() {
+            TIER_BONUSES.save(deps.storage, user_addr, &(reserved + granted))?;
+            TIER_BONUS_RESERVED.save(deps.storage, &(total_reserved + granted))?;
+        }
+        response = response.add_attribute("tier_bonus", granted);
+    }
+
+    // A partial fill means the remaining capacity is too small to matter, so close the sale,
+    // provided the soft cap was met. Otherwise it settles at the end time like any other.
+    let filled =
+        (!oversubscribable && state.total_usdc_raised >= sale_capacity) || !excess.amount.is_zero();
+    if filled && state.total_usdc_raised >= config.soft_cap {
         state.sale_status = SaleStatus::Succeeded;
+        state.clearing_price = config.clearing_price(state.total_usdc_raised);
     }
     STATE.save(deps.storage, &state)?;
 
-    Ok(Response::new()
+    Ok(response
+        .add_messages(transfer_assets_msgs(std::slice::from_ref(&excess), &buyer)?)
         .add_attribute("action", "buy")
         .add_attribute("buyer", user_addr.to_string())
-        .add_attribute("paid_denom", payment.denom)
+        .add_attribute("round", round.name)
+        .add_attribute("paid_denom", payment.info.key())
         .add_attribute("paid_amount", payment.amount)
+        .add_attribute("accepted_amount", accepted.amount)
+        .add_attribute("refunded_amount", excess.amount)
         .add_attribute("usdc_value_added", usdc_value))
 }
 
+/// Admits `user` if they are already whitelisted, or if `whitelist_proof` proves their
+/// inclusion in the registered Merkle whitelist. Proven addresses (and any cap committed
+/// to in their leaf) are cached so later purchases don't need to resubmit the proof.
+fn ensure_whitelisted(
+    deps: DepsMut,
+    user: &Addr,
+    whitelist_proof: Option<WhitelistProof>,
+) -> Result<(), ContractError> {
+    if WHITELIST.has(deps.storage, user) {
+        return Ok(());
+    }
+    let whitelist_proof = whitelist_proof.ok_or(ContractError::NotInWhitelist {})?;
+    let merkle_root = MERKLE_ROOT
+        .may_load(deps.storage)?
+        .ok_or(ContractError::MerkleRootNotSet {})?;
+    let root = parse_merkle_root(&merkle_root)?;
+    let leaf = leaf_hash(user.as_str(), whitelist_proof.cap);
+    if !verify_merkle_proof(&root, leaf, &whitelist_proof.proof)? {
+        return Err(ContractError::InvalidMerkleProof {});
+    }
+    let entry = WhitelistEntry {
+        cap: whitelist_proof.cap,
+        min: None,
+    };
+    WHITELIST.save(deps.storage, user, &entry)?;
+    Ok(())
+}
+
+/// Verifies a KYC voucher signed for `user` and whitelists them with the voucher's cap.
+/// An existing entry keeps its limits, taking the lower of the two caps, so a voucher never
+/// loosens what the whitelist manager set; an uncapped entry stays bound to the sale-wide and
+/// per-round limits. Each nonce can be redeemed once per buyer.
+fn redeem_kyc_voucher(
+    deps: DepsMut,
+    env: &Env,
+    user: &Addr,
+    voucher: KycVoucher,
+) -> Result<(), ContractError> {
+    let signer = KYC_SIGNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::KycSignerNotSet {})?;
+    if env.block.time.seconds() >= voucher.expiry {
+        return Err(ContractError::VoucherExpired {});
+    }
+    if USED_VOUCHER_NONCES.has(deps.storage, (user, voucher.nonce)) {
+        return Err(ContractError::VoucherAlreadyUsed {
+            nonce: voucher.nonce,
+        });
+    }
+    let hash = voucher_hash(
+        env.contract.address.as_str(),
+        user.as_str(),
+        voucher.cap,
+        voucher.expiry,
+        voucher.nonce,
+    );
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &voucher.signature, &signer)
+        .unwrap_or(false);
+    if !valid {
+        return Err(ContractError::InvalidVoucherSignature {});
+    }
+    USED_VOUCHER_NONCES.save(deps.storage, (user, voucher.nonce), &true)?;
+    let entry = match WHITELIST.may_load(deps.storage, user)? {
+        Some(mut entry) => {
+            if let (Some(existing), Some(granted)) = (entry.cap, voucher.cap) {
+                entry.cap = Some(existing.min(granted));
+            }
+            entry
+        }
+        None => WhitelistEntry {
+            cap: voucher.cap,
+            min: None,
+        },
+    };
+    WHITELIST.save(deps.storage, user, &entry)?;
+    Ok(())
+}
+
 fn _end_sale_if_over(deps: DepsMut, env: Env) -> Result<State, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let mut state = STATE.load(deps.storage)?;
-    if state.sale_status == SaleStatus::Active && env.block.time.seconds() >= config.end_time {
-        state.sale_status = if state.total_usdc_raised >= config.soft_cap {
+    let status = settled_status(&config, &state, env.block.time.seconds());
+    if status != state.sale_status {
+        if status == SaleStatus::Succeeded {
+            state.clearing_price = config.clearing_price(state.total_usdc_raised);
+        }
+        state.sale_status = status;
+        STATE.save(deps.storage, &state)?;
+    }
+    Ok(state)
+}
+
+/// The status the sale is in at `now`, settling an `Active` sale whose window has closed
+/// or whose Dutch auction price has fallen to what has been raised.
+fn settled_status(config: &Config, state: &State, now: u64) -> SaleStatus {
+    let sold_out = config
+        .auction_capacity(now)
+        .is_some_and(|capacity| state.total_usdc_raised >= capacity);
+    if state.sale_status == SaleStatus::Active && (now >= config.end_time || sold_out) {
+        if state.total_usdc_raised >= config.soft_cap {
             SaleStatus::Succeeded
         } else {
             SaleStatus::Failed
-        };
-        STATE.save(deps.storage, &state)?;
+        }
+    } else {
+        state.sale_status.clone()
     }
-    Ok(state)
 }
 
 pub fn execute_claim_tokens(
@@ -196,29 +792,24 @@ pub fn execute_claim_tokens(
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let state = _end_sale_if_over(deps.branch(), env)?;
+    let mut state = _end_sale_if_over(deps.branch(), env.clone())?;
     if state.sale_status != SaleStatus::Succeeded {
         return Err(ContractError::SoftCapNotReached {});
     }
-    let total_usdc_value = get_total_usdc_value(deps.as_ref(), &info.sender)?;
-    if total_usdc_value.is_zero() {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_claims_open(&config, &env)?;
+    let settlement = settle_claim(deps.branch(), &config, &mut state, &info.sender, &env)?;
+    if settlement.messages.is_empty() {
         return Err(ContractError::NothingToClaim {});
     }
-    let config = CONFIG.load(deps.storage)?;
-    let eho_to_send = total_usdc_value.multiply_ratio(Uint128::new(1_000_000), config.eho_price);
-    CONTRIBUTIONS.remove(deps.storage, &info.sender);
-    let transfer_msg = Cw20ExecuteMsg::Transfer {
-        recipient: info.sender.to_string(),
-        amount: eho_to_send,
-    };
-    let wasm_msg = WasmMsg::Execute {
-        contract_addr: config.eho_token_address.to_string(),
-        msg: to_json_binary(&transfer_msg)?,
-        funds: vec![],
-    };
+    STATE.save(deps.storage, &state)?;
+    let refunded: Vec<String> = settlement.refund.iter().map(|a| a.to_string()).collect();
     Ok(Response::new()
-        .add_message(CosmosMsg::Wasm(wasm_msg))
-        .add_attribute("action", "claim_tokens"))
+        .add_messages(settlement.messages)
+        .add_attribute("action", "claim_tokens")
+        .add_attribute("amount", settlement.eho)
+        .add_attribute("locked", settlement.locked)
+        .add_attribute("refunded", refunded.join(",")))
 }
 
 pub fn execute_request_refund(
@@ -226,39 +817,256 @@ pub fn execute_request_refund(
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let state = _end_sale_if_over(deps.branch(), env)?;
-    if state.sale_status != SaleStatus::Failed {
+    let mut state = _end_sale_if_over(deps.branch(), env.clone())?;
+    if state.sale_status != SaleStatus::Failed && state.sale_status != SaleStatus::Cancelled {
         return Err(ContractError::SaleNotSucceeded {});
     }
-    let user_contributions = CONTRIBUTIONS.load(deps.storage, &info.sender)?;
-    if user_contributions.is_empty() {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_claims_open(&config, &env)?;
+    let refund = settle_refund(deps.branch(), &config, &mut state, &info.sender)?;
+    if refund.is_empty() {
         return Err(ContractError::NothingToRefund {});
     }
-    CONTRIBUTIONS.remove(deps.storage, &info.sender);
-    let refund_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: user_contributions,
-    };
+    STATE.save(deps.storage, &state)?;
     Ok(Response::new()
-        .add_message(CosmosMsg::Bank(refund_msg))
+        .add_messages(transfer_assets_msgs(&refund, &info.sender)?)
         .add_attribute("action", "request_refund"))
 }
 
-pub fn execute_end_sale(
+/// Pushes claims to the next `limit` contributors or referral bonus holders after the stored
+/// cursor, as if each had called `ClaimTokens`. The cursor restarts from the beginning once
+/// every one of them has been visited, so later batches release newly vested tokens.
+pub fn execute_distribute_batch(
     mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    limit: u32,
 ) -> Result<Response, ContractError> {
+    let mut state = _end_sale_if_over(deps.branch(), env.clone())?;
+    if state.sale_status != SaleStatus::Succeeded {
+        return Err(ContractError::SoftCapNotReached {});
+    }
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    ensure_claims_open(&config, &env)?;
+    let (users, finished) = next_batch(deps.branch(), &DISTRIBUTION_CURSOR, limit, true)?;
+    let mut response = Response::new();
+    let mut distributed = Uint128::zero();
+    let mut recipients = 0u32;
+    for user in &users {
+        let settlement = settle_claim(deps.branch(), &config, &mut state, user, &env)?;
+        if !settlement.messages.is_empty() {
+            recipients += 1;
+            distributed += settlement.eho;
+            response = response.add_messages(settlement.messages);
+        }
+    }
+    STATE.save(deps.storage, &state)?;
+    Ok(response
+        .add_attribute("action", "distribute_batch")
+        .add_attribute("processed", users.len().to_string())
+        .add_attribute("recipients", recipients.to_string())
+        .add_attribute("amount", distributed)
+        .add_attribute("finished", finished.to_string()))
+}
+
+/// Refunds the next `limit` contributors after the stored cursor of a failed or cancelled sale,
+/// as if each had called `RequestRefund`.
+pub fn execute_refund_batch(
+    mut deps: DepsMut,
+    env: Env,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let mut state = _end_sale_if_over(deps.branch(), env.clone())?;
+    if state.sale_status != SaleStatus::Failed && state.sale_status != SaleStatus::Cancelled {
+        return Err(ContractError::SaleNotSucceeded {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    ensure_claims_open(&config, &env)?;
+    let (users, finished) = next_batch(deps.branch(), &REFUND_CURSOR, limit, false)?;
+    let mut response = Response::new();
+    for user in &users {
+        let refund = settle_refund(deps.branch(), &config, &mut state, user)?;
+        response = response.add_messages(transfer_assets_msgs(&refund, user)?);
+    }
+    STATE.save(deps.storage, &state)?;
+    Ok(response
+        .add_attribute("action", "refund_batch")
+        .add_attribute("refunded", users.len().to_string())
+        .add_attribute("finished", finished.to_string()))
+}
+
+fn ensure_claims_open(config: &Config, env: &Env) -> Result<(), ContractError> {
+    if claim_deadline_passed(config, env.block.time.seconds()) {
+        return Err(ContractError::ClaimDeadlinePassed {});
+    }
+    Ok(())
+}
+
+fn claim_deadline_passed(config: &Config, now: u64) -> bool {
+    config
+        .claim_deadline
+        .is_some_and(|deadline| now >= deadline)
+}
+
+/// Takes the next `limit` contributors after `cursor` and advances it, clearing it once the
+/// last contributor is reached. Also returns whether that happened. With `bonus_holders`,
+/// addresses that only earned referral bonuses are walked in the same address order.
+fn next_batch(
+    deps: DepsMut,
+    cursor: &Item<Addr>,
+    limit: u32,
+    bonus_holders: bool,
+) -> Result<(Vec<Addr>, bool), ContractError> {
+    if limit == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let limit = limit.min(MAX_BATCH_SIZE) as usize;
+    let start_after = cursor.may_load(deps.storage)?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let mut users = CONTRIBUTIONS
+        .keys(deps.storage, start.clone(), None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    if bonus_holders {
+        // The first `limit + 1` of the union are among the first `limit + 1` of each map.
+        let holders = REFERRAL_BONUSES
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        users.extend(holders);
+        users.sort();
+        users.dedup();
+        users.truncate(limit + 1);
+    }
+    let finished = users.len() <= limit;
+    users.truncate(limit);
+    match users.last() {
+        Some(last) if !finished => cursor.save(deps.storage, last)?,
+        _ => cursor.remove(deps.storage),
+    }
+    Ok((users, finished))
+}
+
+/// What a claim by a contributor releases.
+struct ClaimSettlement {
+    /// The EHO transferred.
+    eho: Uint128,
+    /// The part of the allocation still locked by vesting.
+    locked: Uint128,
+    /// The unused contribution returned in an oversubscribed `FairLaunch` sale, or what was paid
+    /// above a Dutch auction's clearing price.
+    refund: Vec<Asset>,
+    /// The transfers paying out the above. Empty when there was nothing to claim.
+    messages: Vec<CosmosMsg>,
+}
+
+/// Records a claim of everything `user` can currently claim and builds the transfers for it.
+fn settle_claim(
+    deps: DepsMut,
+    config: &Config,
+    state: &mut State,
+    user: &Addr,
+    env: &Env,
+) -> StdResult<ClaimSettlement> {
+    let claimable = get_claimable(deps.as_ref(), config, state, user, env)?;
+    let eho_to_send = claimable.claimable;
+    let excess_refund = get_excess_refund(deps.as_ref(), config, state, user)?;
+
+    let mut messages = vec![];
+    if !eho_to_send.is_zero() {
+        if claimable.claimed.is_zero() {
+            state.claimed_count += 1;
+        }
+        state.total_eho_claimed += eho_to_send;
+        CLAIMED.save(deps.storage, user, &(claimable.claimed + eho_to_send))?;
+        let transfer_msg = match config.distribution {
+            Distribution::Transfer => Cw20ExecuteMsg::Transfer {
+                recipient: user.to_string(),
+                amount: eho_to_send,
+            },
+            Distribution::Mint => Cw20ExecuteMsg::Mint {
+                recipient: user.to_string(),
+                amount: eho_to_send,
+            },
+        };
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.eho_token_address.to_string(),
+            msg: to_json_binary(&transfer_msg)?,
+            funds: vec![],
+        }));
+    }
+    if !excess_refund.is_empty() {
+        EXCESS_REFUNDED.save(deps.storage, user, &true)?;
+        for asset in &excess_refund {
+            EXCESS_REFUNDED_TOTALS.update(
+                deps.storage,
+                asset.info.key(),
+                |refunded| -> StdResult<_> { Ok(refunded.unwrap_or_default() + asset.amount) },
+            )?;
+        }
+        messages.extend(transfer_assets_msgs(&excess_refund, user)?);
+    }
+    Ok(ClaimSettlement {
+        eho: eho_to_send,
+        locked: claimable.locked,
+        refund: excess_refund,
+        messages,
+    })
+}
+
+/// Removes every contribution of `user` after a failed or cancelled sale and returns the assets
+/// to refund them, if any.
+fn settle_refund(
+    mut deps: DepsMut,
+    config: &Config,
+    state: &mut State,
+    user: &Addr,
+) -> StdResult<Vec<Asset>> {
+    let user_contributions = CONTRIBUTIONS
+        .may_load(deps.storage, user)?
+        .unwrap_or_default();
+    if user_contributions.is_empty() {
+        return Ok(vec![]);
     }
+    CONTRIBUTIONS.remove(deps.storage, user);
+    state.refunded_count += 1;
+    clear_round_contributions(deps.branch(), config, user);
+    for contribution in &user_contributions {
+        AUCTION_BIDS.remove(deps.storage, (user, contribution.info.key()));
+    }
+    Ok(user_contributions.iter().map(Contribution::asset).collect())
+}
+
+pub fn execute_end_sale(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.as_ref(), &info.sender, Role::Pauser)?;
     let final_state = _end_sale_if_over(deps.branch(), env)?;
     Ok(Response::new()
         .add_attribute("action", "admin_end_sale")
         .add_attribute("final_status", format!("{:?}", final_state.sale_status)))
 }
 
+pub fn execute_cancel_sale(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.as_ref(), &info.sender, Role::Owner)?;
+    // A sale whose window has already closed is settled rather than cancelled.
+    let mut state = _end_sale_if_over(deps.branch(), env)?;
+    if state.sale_status != SaleStatus::Pending && state.sale_status != SaleStatus::Active {
+        return Err(ContractError::SaleHasEnded {});
+    }
+    state.sale_status = SaleStatus::Cancelled;
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("action", "cancel_sale")
+        .add_attribute("reason", reason))
+}
+
 pub fn execute_reclaim_unsold_tokens(
     deps: DepsMut,
     env: Env,
@@ -268,11 +1076,9 @@ pub fn execute_reclaim_unsold_tokens(
     let state = STATE.load(deps.storage)?;
 
     // --- Validation ---
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_role(deps.as_ref(), &info.sender, Role::Treasurer)?;
 
-    if (state.sale_status != SaleStatus::Succeeded) && (state.sale_status != SaleStatus::Failed) {
+    if matches!(state.sale_status, SaleStatus::Pending | SaleStatus::Active) {
         return Err(ContractError::SaleIsStillActive {});
     }
 
@@ -284,15 +1090,18 @@ pub fn execute_reclaim_unsold_tokens(
         .querier
         .query_wasm_smart(config.eho_token_address.clone(), &balance_query)?;
 
-    let remaining_balance = balance_response.balance;
+    // Allocations not claimed yet stay in the contract until the claim deadline.
+    let outstanding =
+        get_outstanding_eho(deps.as_ref(), &config, &state, env.block.time.seconds())?;
+    let remaining_balance = balance_response.balance.saturating_sub(outstanding);
 
     if remaining_balance.is_zero() {
         return Err(ContractError::NoTokensToReclaim {});
     }
 
-    // Create the CW20 Transfer message to send the remaining EHO tokens back to the admin.
+    // Create the CW20 Transfer message to send the remaining EHO tokens to the treasury.
     let reclaim_msg = Cw20ExecuteMsg::Transfer {
-        recipient: config.admin.to_string(),
+        recipient: config.treasury.to_string(),
         amount: remaining_balance,
     };
     let wasm_msg = WasmMsg::Execute {
@@ -307,50 +1116,111 @@ pub fn execute_reclaim_unsold_tokens(
         .add_attribute("amount_reclaimed", remaining_balance))
 }
 
-pub fn execute_withdraw_funds(
+pub fn execute_sweep_unclaimed(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    ensure_role(deps.as_ref(), &info.sender, Role::Treasurer)?;
+    if config.claim_deadline.is_none() {
+        return Err(ContractError::ConfigError {
+            details: "No claim deadline is configured".to_string(),
+        });
     }
-    let state = _end_sale_if_over(deps.branch(), env.clone())?;
-    if state.sale_status != SaleStatus::Succeeded {
-        return Err(ContractError::SaleNotSucceeded {});
+    if !claim_deadline_passed(&config, env.block.time.seconds()) {
+        return Err(ContractError::ClaimDeadlineNotReached {});
     }
+    _end_sale_if_over(deps.branch(), env.clone())?;
+    // Nothing is owed to contributors any more, so every balance goes.
     let contract_addr = env.contract.address;
-    let mut funds_to_withdraw: Vec<Coin> = vec![];
-    for denom in config.accepted_payment_denoms {
-        let balance = deps.querier.query_balance(contract_addr.clone(), denom)?;
-        if !balance.amount.is_zero() {
-            funds_to_withdraw.push(balance);
+    let mut assets: Vec<AssetInfo> = vec![AssetInfo::Cw20 {
+        contract_addr: config.eho_token_address.clone(),
+    }];
+    for item in TOTAL_RAISED_BY_ASSET.range(deps.storage, None, None, Order::Ascending) {
+        assets.push(item?.1.info);
+    }
+    let mut swept: Vec<Asset> = vec![];
+    for asset_info in assets {
+        let amount = query_asset_balance(deps.as_ref(), &asset_info, &contract_addr)?;
+        if !amount.is_zero() {
+            swept.push(Asset {
+                info: asset_info,
+                amount,
+            });
         }
     }
-    if funds_to_withdraw.is_empty() {
+    if swept.is_empty() {
         return Err(ContractError::NoFundsToWithdraw {});
     }
-    let withdraw_msg = BankMsg::Send {
-        to_address: config.admin.to_string(),
-        amount: funds_to_withdraw,
-    };
+    let swept_attr: Vec<String> = swept.iter().map(|asset| asset.to_string()).collect();
     Ok(Response::new()
-        .add_message(CosmosMsg::Bank(withdraw_msg))
+        .add_messages(transfer_assets_msgs(&swept, &config.treasury)?)
+        .add_attribute("action", "sweep_unclaimed")
+        .add_attribute("swept", swept_attr.join(",")))
+}
+
+pub fn execute_withdraw_funds(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.as_ref(), &info.sender, Role::Treasurer)?;
+    let state = _end_sale_if_over(deps.branch(), env.clone())?;
+    if state.sale_status != SaleStatus::Succeeded {
+        return Err(ContractError::SaleNotSucceeded {});
+    }
+    // Every asset ever raised is withdrawn, including ones whose rate has since been removed.
+    let contract_addr = env.contract.address;
+    let raised_assets: Vec<AssetInfo> = TOTAL_RAISED_BY_ASSET
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, raised)| raised.info))
+        .collect::<StdResult<_>>()?;
+    let mut funds_to_withdraw: Vec<Asset> = vec![];
+    for asset_info in raised_assets {
+        let balance = query_asset_balance(deps.as_ref(), &asset_info, &contract_addr)?;
+        // Oversubscription refunds that contributors have not claimed yet stay in the contract.
+        let reserved = get_reserved_excess(deps.as_ref(), &config, &state, &asset_info)?;
+        let amount = balance.saturating_sub(reserved);
+        if !amount.is_zero() {
+            funds_to_withdraw.push(Asset {
+                info: asset_info,
+                amount,
+            });
+        }
+    }
+    if funds_to_withdraw.is_empty() {
+        return Err(ContractError::NoFundsToWithdraw {});
+    }
+    Ok(Response::new()
+        .add_messages(transfer_assets_msgs(&funds_to_withdraw, &config.treasury)?)
         .add_attribute("action", "withdraw_funds"))
 }
 
 pub fn execute_add_to_whitelist(
     deps: DepsMut,
     info: MessageInfo,
-    addresses: Vec<String>,
+    entries: Vec<WhitelistAddition>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
-    for addr_str in addresses {
-        WHITELIST.save(deps.storage, &deps.api.addr_validate(&addr_str)?, &true)?;
+    ensure_role(deps.as_ref(), &info.sender, Role::WhitelistManager)?;
+    for addition in entries {
+        if let (Some(cap), Some(min)) = (addition.cap, addition.min) {
+            if min > cap {
+                return Err(ContractError::ConfigError {
+                    details: format!("Minimum of {} exceeds its cap", addition.address),
+                });
+            }
+        }
+        let entry = WhitelistEntry {
+            cap: addition.cap,
+            min: addition.min,
+        };
+        WHITELIST.save(
+            deps.storage,
+            &deps.api.addr_validate(&addition.address)?,
+            &entry,
+        )?;
     }
     Ok(Response::new().add_attribute("action", "add_to_whitelist"))
 }
@@ -360,42 +1230,191 @@ pub fn execute_remove_from_whitelist(
     info: MessageInfo,
     addresses: Vec<String>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_role(deps.as_ref(), &info.sender, Role::WhitelistManager)?;
     for addr_str in addresses {
-        WHITELIST.remove(deps.storage, &deps.api.addr_validate(&addr_str)?);
+        let addr = deps.api.addr_validate(&addr_str)?;
+        WHITELIST.remove(deps.storage, &addr);
     }
     Ok(Response::new().add_attribute("action", "remove_from_whitelist"))
 }
 
-pub fn execute_update_admin(
+pub fn execute_update_merkle_root(
     deps: DepsMut,
     info: MessageInfo,
-    new_admin: String,
+    merkle_root: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.as_ref(), &info.sender, Role::WhitelistManager)?;
+    parse_merkle_root(&merkle_root)?;
+    MERKLE_ROOT.save(deps.storage, &merkle_root)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_merkle_root")
+        .add_attribute("merkle_root", merkle_root))
+}
+
+pub fn execute_update_kyc_signer(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
 ) -> Result<Response, ContractError> {
-    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
-        if info.sender != config.admin {
-            return Err(ContractError::Unauthorized {});
+    ensure_role(deps.as_ref(), &info.sender, Role::WhitelistManager)?;
+    match &pubkey {
+        Some(pubkey) => {
+            // Compressed (33 bytes) or uncompressed (65 bytes) SEC1 encoding
+            if pubkey.len() != 33 && pubkey.len() != 65 {
+                return Err(ContractError::ConfigError {
+                    details: "KYC signer must be a secp256k1 public key".to_string(),
+                });
+            }
+            KYC_SIGNER.save(deps.storage, pubkey)?;
         }
-        config.admin = deps.api.addr_validate(&new_admin)?;
-        Ok(config)
-    })?;
+        None => KYC_SIGNER.remove(deps.storage),
+    }
     Ok(Response::new()
-        .add_attribute("action", "update_admin")
+        .add_attribute("action", "update_kyc_signer")
+        .add_attribute(
+            "pubkey",
+            pubkey.map(|pubkey| pubkey.to_base64()).unwrap_or_default(),
+        ))
+}
+
+pub fn execute_propose_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_admin: String,
+    expiry: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    if expiry.is_some_and(|expiry| expiry <= env.block.time.seconds()) {
+        return Err(ContractError::AdminProposalExpired {});
+    }
+    let pending = PendingAdmin {
+        address: deps.api.addr_validate(&new_admin)?,
+        expiry,
+    };
+    PENDING_ADMIN.save(deps.storage, &pending)?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_admin")
         .add_attribute("new_admin", new_admin))
 }
 
-pub fn execute_update_pause(
+pub fn execute_accept_admin(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoAdminProposal {})?;
+    if info.sender != pending.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending
+        .expiry
+        .is_some_and(|expiry| expiry <= env.block.time.seconds())
+    {
+        return Err(ContractError::AdminProposalExpired {});
+    }
+    ADMIN.set(deps.branch(), Some(pending.address.clone()))?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("new_admin", pending.address))
+}
+
+pub fn execute_cancel_admin_proposal(
     deps: DepsMut,
     info: MessageInfo,
-    pause: bool,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.admin {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    if !PENDING_ADMIN.exists(deps.storage) {
+        return Err(ContractError::NoAdminProposal {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new().add_attribute("action", "cancel_admin_proposal"))
+}
+
+pub fn execute_renounce_admin(
+    mut deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    ADMIN.set(deps.branch(), None)?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new().add_attribute("action", "renounce_admin"))
+}
+
+/// Checks that `sender` is the current admin (the owner).
+fn ensure_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    ADMIN.assert_admin(deps, sender).map_err(|err| match err {
+        AdminError::Std(err) => err.into(),
+        AdminError::NotAdmin {} => ContractError::Unauthorized {},
+    })
+}
+
+/// Checks that `sender` holds `role`. The owner implicitly holds every role.
+fn ensure_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if ADMIN.is_admin(deps, sender)? {
+        return Ok(());
+    }
+    let roles = ROLES.may_load(deps.storage, sender)?.unwrap_or_default();
+    if role == Role::Owner || !roles.contains(&role) {
         return Err(ContractError::Unauthorized {});
     }
+    Ok(())
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    if role == Role::Owner {
+        return Err(ContractError::ConfigError {
+            details: "Ownership is transferred with ProposeAdmin".to_string(),
+        });
+    }
+    let addr = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.may_load(deps.storage, &addr)?.unwrap_or_default();
+    if !roles.contains(&role) {
+        roles.push(role);
+        ROLES.save(deps.storage, &addr, &roles)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("address", addr)
+        .add_attribute("role", role.to_string()))
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.may_load(deps.storage, &addr)?.unwrap_or_default();
+    roles.retain(|held| *held != role);
+    if roles.is_empty() {
+        ROLES.remove(deps.storage, &addr);
+    } else {
+        ROLES.save(deps.storage, &addr, &roles)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("address", addr)
+        .add_attribute("role", role.to_string()))
+}
+
+pub fn execute_update_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    pause: bool,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.as_ref(), &info.sender, Role::Pauser)?;
     STATE.update(deps.storage, |mut state| -> StdResult<_> {
         state.paused = pause;
         Ok(state)
@@ -405,25 +1424,352 @@ pub fn execute_update_pause(
         .add_attribute("paused", pause.to_string()))
 }
 
+pub fn execute_update_rates(
+    deps: DepsMut,
+    info: MessageInfo,
+    rates: Vec<Rate>,
+    cw20_rates: Vec<Rate>,
+    remove: Vec<String>,
+    remove_caps: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.as_ref(), &info.sender, Role::Owner)?;
+    for key in &remove {
+        EXCHANGE_RATES.remove(deps.storage, key);
+        PAYMENT_DECIMALS.remove(deps.storage, key);
+        DENOM_CAPS.remove(deps.storage, key);
+        config.accepted_payment_denoms.retain(|denom| denom != key);
+        config
+            .accepted_cw20_tokens
+            .retain(|token| token.as_str() != key);
+    }
+    for key in &remove_caps {
+        DENOM_CAPS.remove(deps.storage, key);
+    }
+    for rate in rates {
+        save_rate(deps.storage, &rate.denom, &rate)?;
+        if !config.accepted_payment_denoms.contains(&rate.denom) {
+            config.accepted_payment_denoms.push(rate.denom);
+        }
+    }
+    for rate in cw20_rates {
+        let token = deps.api.addr_validate(&rate.denom)?;
+        save_rate(deps.storage, token.as_str(), &rate)?;
+        if !config.accepted_cw20_tokens.contains(&token) {
+            config.accepted_cw20_tokens.push(token);
+        }
+    }
+    if config.accepted_payment_denoms.is_empty() && config.accepted_cw20_tokens.is_empty() {
+        return Err(ContractError::ConfigError {
+            details: "At least one accepted rate must be provided".to_string(),
+        });
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_rates")
+        .add_attribute("removed", remove.join(",")))
+}
+
+pub fn execute_update_oracle(
+    deps: DepsMut,
+    info: MessageInfo,
+    oracle: Option<OracleConfig>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.as_ref(), &info.sender, Role::Owner)?;
+    config.oracle = oracle
+        .map(|oracle| validate_oracle(deps.as_ref(), oracle))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+    let oracle_attr = config
+        .oracle
+        .map(|oracle| oracle.contract.to_string())
+        .unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "update_oracle")
+        .add_attribute("oracle", oracle_attr))
+}
+
+pub fn execute_update_config(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_role(deps.as_ref(), &info.sender, Role::Owner)?;
+    let mut state = _end_sale_if_over(deps.branch(), env.clone())?;
+    let now = env.block.time.seconds();
+    let started = state.sale_status != SaleStatus::Pending || now >= config.start_time;
+    if !matches!(state.sale_status, SaleStatus::Pending | SaleStatus::Active) {
+        return Err(ContractError::SaleHasEnded {});
+    }
+
+    let mut changes = vec![];
+    let mut record = |field: &str, old: String, new: String| {
+        if old != new {
+            changes.push(ParamChange {
+                field: field.to_string(),
+                old_value: old,
+                new_value: new,
+            });
+        }
+    };
+    if let Some(treasury) = &update.treasury {
+        let treasury = deps.api.addr_validate(treasury)?;
+        record(
+            "treasury",
+            config.treasury.to_string(),
+            treasury.to_string(),
+        );
+        config.treasury = treasury;
+    }
+    if started {
+        // Once buying has opened, only the treasury and a single, bounded extension of the
+        // end time may change.
+        let extension_only = update
+            == ConfigUpdate {
+                end_time: update.end_time,
+                treasury: update.treasury.clone(),
+                ..Default::default()
+            };
+        if !extension_only {
+            return Err(ContractError::ConfigError {
+                details: "Only the end time can be extended once the sale has started".to_string(),
+            });
+        }
+        if let Some(end_time) = update.end_time {
+            if state.end_time_extended {
+                return Err(ContractError::ConfigError {
+                    details: "The end time has already been extended".to_string(),
+                });
+            }
+            if matches!(config.sale_mode, SaleMode::DutchAuction { .. }) {
+                return Err(ContractError::ConfigError {
+                    details: "A running Dutch auction's schedule cannot be extended".to_string(),
+                });
+            }
+            let max_end_time = config.end_time.saturating_add(MAX_END_TIME_EXTENSION);
+            if end_time <= config.end_time || end_time > max_end_time {
+                return Err(ContractError::ConfigError {
+                    details: format!(
+                        "The end time can only be extended by up to {} seconds",
+                        MAX_END_TIME_EXTENSION
+                    ),
+                });
+            }
+            record(
+                "end_time",
+                config.end_time.to_string(),
+                end_time.to_string(),
+            );
+            // Purchases resolve against the round windows, so the final round moves along.
+            let extension = end_time - config.end_time;
+            if let Some(round) = config.rounds.last_mut() {
+                let round_end_time = round.end_time + extension;
+                record(
+                    &format!("{}.end_time", round.name),
+                    round.end_time.to_string(),
+                    round_end_time.to_string(),
+                );
+                round.end_time = round_end_time;
+            }
+            config.end_time = end_time;
+            state.end_time_extended = true;
+            STATE.save(deps.storage, &state)?;
+        }
+    } else {
+        if let Some(start_time) = update.start_time {
+            record(
+                "start_time",
+                config.start_time.to_string(),
+                start_time.to_string(),
+            );
+            config.start_time = start_time;
+        }
+        if let Some(end_time) = update.end_time {
+            record(
+                "end_time",
+                config.end_time.to_string(),
+                end_time.to_string(),
+            );
+            config.end_time = end_time;
+        }
+        if let Some(soft_cap) = update.soft_cap {
+            record(
+                "soft_cap",
+                config.soft_cap.to_string(),
+                soft_cap.to_string(),
+            );
+            config.soft_cap = soft_cap;
+        }
+        if let Some(hard_cap) = update.hard_cap {
+            record(
+                "hard_cap",
+                config.hard_cap.to_string(),
+                hard_cap.to_string(),
+            );
+            config.hard_cap = hard_cap;
+        }
+        if let Some(eho_price) = update.eho_price {
+            record(
+                "eho_price",
+                config.eho_price.to_string(),
+                eho_price.to_string(),
+            );
+            config.eho_price = eho_price;
+        }
+        if let Some(max_contribution) = update.max_contribution_per_user {
+            record(
+                "max_contribution_per_user",
+                config.max_contribution_per_user.to_string(),
+                max_contribution.to_string(),
+            );
+            config.max_contribution_per_user = max_contribution;
+        }
+        if let Some(claim_deadline) = update.claim_deadline {
+            let old = config
+                .claim_deadline
+                .map(|d| d.to_string())
+                .unwrap_or_default();
+            record("claim_deadline", old, claim_deadline.to_string());
+            config.claim_deadline = Some(claim_deadline);
+        }
+    }
+    validate_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let id = CONFIG_CHANGES
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+    let fields: Vec<String> = changes.iter().map(|change| change.field.clone()).collect();
+    CONFIG_CHANGES.save(
+        deps.storage,
+        id,
+        &ConfigChange {
+            id,
+            changed_by: info.sender,
+            changed_at: now,
+            changes,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("change_id", id.to_string())
+        .add_attribute("fields", fields.join(",")))
+}
+
+fn validate_oracle(deps: Deps, oracle: OracleConfig) -> Result<OracleConfig, ContractError> {
+    if oracle.max_staleness == 0 {
+        return Err(ContractError::ConfigError {
+            details: "Oracle max staleness must be greater than zero".to_string(),
+        });
+    }
+    Ok(OracleConfig {
+        contract: deps.api.addr_validate(oracle.contract.as_str())?,
+        ..oracle
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
-        QueryMsg::State {} => to_json_binary(&STATE.load(deps.storage)?),
+        QueryMsg::State {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let mut state = STATE.load(deps.storage)?;
+            state.access_mode = config.access_mode(env.block.time.seconds());
+            to_json_binary(&state)
+        }
         QueryMsg::AcceptedRates {} => {
-            let rates: StdResult<Vec<Rate>> = EXCHANGE_RATES
+            let rates: StdResult<Vec<AcceptedRateResponse>> = EXCHANGE_RATES
                 .range(deps.storage, None, None, Order::Ascending)
                 .map(|item| {
                     let (denom, rate) = item?;
-                    Ok(Rate { denom, rate })
+                    let decimals = payment_decimals(deps.storage, &denom)?;
+                    let cap = DENOM_CAPS.may_load(deps.storage, &denom)?;
+                    let raised = RAISED_VALUE_BY_DENOM
+                        .may_load(deps.storage, &denom)?
+                        .unwrap_or_default();
+                    Ok(AcceptedRateResponse {
+                        remaining: cap.map(|cap| cap.saturating_sub(raised)),
+                        denom,
+                        rate,
+                        decimals,
+                        cap,
+                    })
                 })
                 .collect();
             to_json_binary(&rates?)
         }
         QueryMsg::IsWhitelisted { address } => {
             let addr = deps.api.addr_validate(&address)?;
-            let is_whitelisted = WHITELIST.load(deps.storage, &addr).unwrap_or(false);
-            to_json_binary(&is_whitelisted)
+            let entry = WHITELIST.may_load(deps.storage, &addr)?;
+            to_json_binary(&IsWhitelistedResponse {
+                whitelisted: entry.is_some(),
+                cap: entry.as_ref().and_then(|entry| entry.cap),
+                min: entry.and_then(|entry| entry.min),
+            })
+        }
+        QueryMsg::MerkleRoot {} => to_json_binary(&MERKLE_ROOT.may_load(deps.storage)?),
+        QueryMsg::ConfigChanges { start_after, limit } => {
+            to_json_binary(&query_config_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::Admin {} => to_json_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::PendingAdmin {} => to_json_binary(&PENDING_ADMIN.may_load(deps.storage)?),
+        QueryMsg::Roles { start_after, limit } => {
+            to_json_binary(&query_roles(deps, start_after, limit)?)
+        }
+        QueryMsg::KycSigner {} => to_json_binary(&KYC_SIGNER.may_load(deps.storage)?),
+        QueryMsg::VerifyWhitelistProof {
+            address,
+            whitelist_proof,
+        } => {
+            let addr = deps.api.addr_validate(&address)?;
+            let is_valid = match MERKLE_ROOT.may_load(deps.storage)? {
+                Some(merkle_root) => verify_merkle_proof(
+                    &parse_merkle_root(&merkle_root)?,
+                    leaf_hash(addr.as_str(), whitelist_proof.cap),
+                    &whitelist_proof.proof,
+                )?,
+                None => false,
+            };
+            to_json_binary(&is_valid)
+        }
+        QueryMsg::CurrentRound {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let current_round = match config.active_round(env.block.time.seconds()) {
+                Some((index, round)) => Some(RoundResponse {
+                    index,
+                    round,
+                    total_raised: ROUND_TOTALS
+                        .may_load(deps.storage, index)?
+                        .unwrap_or_default(),
+                }),
+                None => None,
+            };
+            to_json_binary(&current_round)
+        }
+        QueryMsg::Rounds {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let rounds: StdResult<Vec<RoundResponse>> = config
+                .sale_rounds()
+                .into_iter()
+                .enumerate()
+                .map(|(index, round)| {
+                    let index = index as u32;
+                    Ok(RoundResponse {
+                        index,
+                        round,
+                        total_raised: ROUND_TOTALS
+                            .may_load(deps.storage, index)?
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect();
+            to_json_binary(&rounds?)
         }
         QueryMsg::TotalContributionOf { address } => {
             let addr = deps.api.addr_validate(&address)?;
@@ -441,29 +1787,520 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let addr = deps.api.addr_validate(&address)?;
             let config = CONFIG.load(deps.storage)?;
 
-            // Get the user's total contributed value in USDC
-            let total_usdc_value = get_total_usdc_value(deps, &addr)?;
+            let state = STATE.load(deps.storage)?;
+
+            // Sum the allocation across rounds using the same logic as the claim function
+            let eho_allocation = get_eho_allocation(deps, &config, &state, &addr)?;
+            let referral_bonus = REFERRAL_BONUSES
+                .may_load(deps.storage, &addr)?
+                .unwrap_or_default();
+            let tier_bonus = TIER_BONUSES
+                .may_load(deps.storage, &addr)?
+                .unwrap_or_default();
+            let refund = get_excess_refund(deps, &config, &state, &addr)?;
+
+            to_json_binary(&AllocationResponse {
+                eho_allocation,
+                referral_bonus: scale_allocation(&config, &state, referral_bonus),
+                tier_bonus: scale_allocation(&config, &state, tier_bonus),
+                refund,
+            })
+        }
+        QueryMsg::CurrentPrice {} => {
+            let config = CONFIG.load(deps.storage)?;
+            let state = STATE.load(deps.storage)?;
+            let now = env.block.time.seconds();
+            let price = match config.auction_price(now) {
+                Some(_) if settled_status(&config, &state, now) == SaleStatus::Succeeded => config
+                    .clearing_price(state.total_usdc_raised)
+                    .unwrap_or_default(),
+                Some(auction_price) => auction_price,
+                None => config
+                    .active_round(now)
+                    .map_or(config.eho_price, |(_, round)| round.eho_price),
+            };
+            to_json_binary(&price)
+        }
+        QueryMsg::AllContributions { start_after, limit } => {
+            to_json_binary(&query_all_contributions(deps, start_after, limit)?)
+        }
+        QueryMsg::AllWhitelisted { start_after, limit } => {
+            to_json_binary(&query_all_whitelisted(deps, start_after, limit)?)
+        }
+        QueryMsg::Funding {} => to_json_binary(&query_funding(deps, env)?),
+        QueryMsg::SaleStats {} => to_json_binary(&query_sale_stats(deps)?),
+        QueryMsg::ReferralStats { start_after, limit } => {
+            to_json_binary(&query_referral_stats(deps, start_after, limit)?)
+        }
+        QueryMsg::ClaimableOf { address } => {
+            let addr = deps.api.addr_validate(&address)?;
+            let config = CONFIG.load(deps.storage)?;
+            let state = STATE.load(deps.storage)?;
+            to_json_binary(&get_claimable(deps, &config, &state, &addr, &env)?)
+        }
+    }
+}
+
+/// Sums the USDC-equivalent value of `user`'s contributions as locked in at purchase time.
+fn get_total_usdc_value(deps: Deps, user: &Addr) -> StdResult<Uint128> {
+    let contributions = CONTRIBUTIONS
+        .may_load(deps.storage, user)?
+        .unwrap_or_default();
+    Ok(contributions.iter().map(|c| c.usdc_value).sum())
+}
+
+fn save_rate(storage: &mut dyn Storage, key: &str, rate: &Rate) -> Result<(), ContractError> {
+    if rate.rate.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if rate.decimals > MAX_PAYMENT_DECIMALS {
+        return Err(ContractError::ConfigError {
+            details: format!(
+                "Payment asset '{}' cannot have more than {} decimals",
+                key, MAX_PAYMENT_DECIMALS
+            ),
+        });
+    }
+    EXCHANGE_RATES.save(storage, key, &rate.rate)?;
+    PAYMENT_DECIMALS.save(storage, key, &rate.decimals)?;
+    if let Some(cap) = rate.cap {
+        if cap.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        DENOM_CAPS.save(storage, key, &cap)?;
+    }
+    Ok(())
+}
+
+fn payment_decimals(storage: &dyn Storage, key: &str) -> StdResult<u8> {
+    Ok(PAYMENT_DECIMALS
+        .may_load(storage, key)?
+        .unwrap_or(DEFAULT_PAYMENT_DECIMALS))
+}
+
+/// Values `amount` base units of an asset with `decimals` decimals, priced at `rate`
+/// USDC (with 6 decimals) per whole token.
+fn usdc_value_of(amount: Uint128, rate: Uint128, decimals: u8) -> Uint128 {
+    amount.multiply_ratio(rate, 10u128.pow(decimals.into()))
+}
+
+/// The balance of the native or CW20 asset `info` held by `holder`.
+fn query_asset_balance(deps: Deps, info: &AssetInfo, holder: &Addr) -> StdResult<Uint128> {
+    match info {
+        AssetInfo::Native { denom } => Ok(deps.querier.query_balance(holder, denom)?.amount),
+        AssetInfo::Cw20 { contract_addr } => {
+            let balance: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+                contract_addr,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: holder.to_string(),
+                },
+            )?;
+            Ok(balance.balance)
+        }
+    }
+}
+
+/// Builds the messages sending `assets` to `recipient`: a single bank send for all native
+/// coins and a CW20 transfer per token contract.
+fn transfer_assets_msgs(assets: &[Asset], recipient: &Addr) -> StdResult<Vec<CosmosMsg>> {
+    let mut coins: Vec<Coin> = vec![];
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for asset in assets.iter().filter(|a| !a.amount.is_zero()) {
+        match &asset.info {
+            AssetInfo::Native { denom } => coins.push(Coin {
+                denom: denom.clone(),
+                amount: asset.amount,
+            }),
+            AssetInfo::Cw20 { contract_addr } => msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: asset.amount,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+    if !coins.is_empty() {
+        msgs.insert(
+            0,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins,
+            }),
+        );
+    }
+    Ok(msgs)
+}
+
+/// Lists contributors with their contributions in address order, a page at a time.
+fn query_all_contributions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllContributionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let contributors = CONTRIBUTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, contributions) = item?;
+            Ok(ContributorInfo {
+                address: address.to_string(),
+                contributions,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AllContributionsResponse { contributors })
+}
+
+fn query_all_whitelisted(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllWhitelistedResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let addresses = WHITELIST
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, _)| address.to_string()))
+        .collect::<StdResult<_>>()?;
+    Ok(AllWhitelistedResponse { addresses })
+}
+
+fn query_roles(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RolesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let members = ROLES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, roles)| RoleMember {
+                address: address.to_string(),
+                roles,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(RolesResponse {
+        owner: ADMIN.get(deps)?.map(|admin| admin.to_string()),
+        members,
+    })
+}
+
+fn query_config_changes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ConfigChangesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let changes = CONFIG_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<_>>()?;
+    Ok(ConfigChangesResponse { changes })
+}
+
+fn query_funding(deps: Deps, env: Env) -> StdResult<FundingResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    let now = env.block.time.seconds();
+    state.sale_status = settled_status(&config, &state, now);
+    Ok(FundingResponse {
+        required: config.required_eho(),
+        deposited: state.tokens_for_sale,
+        liability: get_outstanding_eho(deps, &config, &state, now)?,
+    })
+}
 
-            // Calculate the EHO allocation using the same logic as the claim function
-            let eho_allocation =
-                total_usdc_value.multiply_ratio(Uint128::new(1_000_000), config.eho_price);
+fn query_sale_stats(deps: Deps) -> StdResult<SaleStatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    let raised_by_asset = TOTAL_RAISED_BY_ASSET
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, raised)| raised))
+        .collect::<StdResult<_>>()?;
+    Ok(SaleStatsResponse {
+        contributor_count: state.contributor_count,
+        claimed_count: state.claimed_count,
+        refunded_count: state.refunded_count,
+        total_usdc_raised: state.total_usdc_raised,
+        raised_by_asset,
+        remaining_capacity: config.hard_cap.saturating_sub(state.total_usdc_raised),
+    })
+}
+
+fn query_referral_stats(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ReferralStatsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let referrers = REFERRER_STATS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (referrer, stats) = item?;
+            Ok(ReferrerInfo {
+                referrer: referrer.to_string(),
+                stats,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ReferralStatsResponse { referrers })
+}
 
-            to_json_binary(&eho_allocation)
+/// Sums the EHO allocation of `user` across all rounds, each at its own price, plus referral
+/// and tier bonuses. An oversubscribed `FairLaunch` sale scales every allocation down to fit
+/// the hard cap.
+fn get_eho_allocation(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    user: &Addr,
+) -> StdResult<Uint128> {
+    let purchased = get_purchased_eho(deps, config, user)?;
+    let referral_bonus = REFERRAL_BONUSES
+        .may_load(deps.storage, user)?
+        .unwrap_or_default();
+    let tier_bonus = TIER_BONUSES
+        .may_load(deps.storage, user)?
+        .unwrap_or_default();
+    Ok(scale_allocation(
+        config,
+        state,
+        purchased + referral_bonus + tier_bonus,
+    ))
+}
+
+/// The EHO bought by `user` across rounds, at each round's price, or at the price of each bid
+/// in a Dutch auction.
+fn get_purchased_eho(deps: Deps, config: &Config, user: &Addr) -> StdResult<Uint128> {
+    if let SaleMode::DutchAuction { .. } = config.sale_mode {
+        return AUCTION_BIDS
+            .prefix(user)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, bid)| bid.eho))
+            .sum();
+    }
+    let mut purchased = Uint128::zero();
+    for (index, round) in config.sale_rounds().iter().enumerate() {
+        let round_value = ROUND_CONTRIBUTIONS
+            .may_load(deps.storage, (index as u32, user))?
+            .unwrap_or_default();
+        purchased += round_value.multiply_ratio(Uint128::new(1_000_000), round.eho_price);
+    }
+    Ok(purchased)
+}
+
+/// The EHO owed to contributors: every allocation not claimed yet, until the claim deadline
+/// or for as long as the sale has not failed. Computed from the sale totals, which never round
+/// below the sum of the individual allocations.
+fn get_outstanding_eho(deps: Deps, config: &Config, state: &State, now: u64) -> StdResult<Uint128> {
+    if matches!(
+        state.sale_status,
+        SaleStatus::Failed | SaleStatus::Cancelled
+    ) || claim_deadline_passed(config, now)
+    {
+        return Ok(Uint128::zero());
+    }
+    let purchased = match config.sale_mode {
+        SaleMode::DutchAuction { .. } => AUCTION_BID_TOTALS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, bid)| bid.eho))
+            .sum::<StdResult<Uint128>>()?,
+        _ => {
+            let mut purchased = Uint128::zero();
+            for (index, round) in config.sale_rounds().iter().enumerate() {
+                let round_total = ROUND_TOTALS
+                    .may_load(deps.storage, index as u32)?
+                    .unwrap_or_default();
+                purchased += round_total.multiply_ratio(Uint128::new(1_000_000), round.eho_price);
+            }
+            purchased
         }
+    };
+    let referral_bonuses = REFERRAL_BONUS_TOTAL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let tier_bonuses = TIER_BONUS_RESERVED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let allocated = scale_allocation(config, state, purchased + referral_bonuses + tier_bonuses);
+    Ok(allocated.saturating_sub(state.total_eho_claimed))
+}
+
+fn scale_allocation(config: &Config, state: &State, amount: Uint128) -> Uint128 {
+    if is_oversubscribed(config, state) {
+        return amount.multiply_ratio(config.hard_cap, state.total_usdc_raised);
     }
+    amount
+}
+
+fn add_referral_bonus(storage: &mut dyn Storage, user: &Addr, bonus: Uint128) -> StdResult<()> {
+    if bonus.is_zero() {
+        return Ok(());
+    }
+    REFERRAL_BONUSES.update(storage, user, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + bonus)
+    })?;
+    let total = REFERRAL_BONUS_TOTAL.may_load(storage)?.unwrap_or_default();
+    REFERRAL_BONUS_TOTAL.save(storage, &(total + bonus))?;
+    Ok(())
+}
+
+fn add_auction_bid(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    key: &str,
+    bid: &AuctionBid,
+) -> StdResult<()> {
+    let add = |total: Option<AuctionBid>| -> StdResult<_> {
+        let total = total.unwrap_or_default();
+        Ok(AuctionBid {
+            eho: total.eho + bid.eho,
+            amount_per_price: total.amount_per_price + bid.amount_per_price,
+        })
+    };
+    AUCTION_BIDS.update(storage, (user, key), add)?;
+    AUCTION_BID_TOTALS.update(storage, key, add)?;
+    Ok(())
+}
+
+fn is_oversubscribed(config: &Config, state: &State) -> bool {
+    config.sale_mode == SaleMode::FairLaunch && state.total_usdc_raised > config.hard_cap
 }
 
-fn get_total_usdc_value(deps: Deps, user: &cosmwasm_std::Addr) -> StdResult<Uint128> {
+/// The part of `user`'s contribution returned to them at settlement: by an oversubscribed
+/// `FairLaunch` sale, each asset pro-rata to how far the total raised overshoots the hard cap,
+/// and by a Dutch auction, what each asset paid above the clearing price.
+fn get_excess_refund(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    user: &Addr,
+) -> StdResult<Vec<Asset>> {
+    if EXCESS_REFUNDED.has(deps.storage, user) {
+        return Ok(vec![]);
+    }
     let contributions = CONTRIBUTIONS
         .may_load(deps.storage, user)?
         .unwrap_or_default();
-    let mut total_value = Uint128::zero();
-    for coin in contributions {
-        let rate = EXCHANGE_RATES.load(deps.storage, &coin.denom)?;
-        let value = coin.amount.multiply_ratio(rate, Uint128::new(1_000_000));
-        total_value += value;
+    if let Some(clearing_price) = config.clearing_price(state.total_usdc_raised) {
+        let mut refund = vec![];
+        for contribution in contributions {
+            let bid = AUCTION_BIDS
+                .may_load(deps.storage, (user, contribution.info.key()))?
+                .unwrap_or_default();
+            let amount = contribution
+                .amount
+                .saturating_sub(bid.settled_amount(clearing_price));
+            if !amount.is_zero() {
+                refund.push(Asset {
+                    info: contribution.info,
+                    amount,
+                });
+            }
+        }
+        return Ok(refund);
+    }
+    if !is_oversubscribed(config, state) {
+        return Ok(vec![]);
+    }
+    let excess = state.total_usdc_raised - config.hard_cap;
+    Ok(contributions
+        .into_iter()
+        .map(|contribution| Asset {
+            amount: contribution
+                .amount
+                .multiply_ratio(excess, state.total_usdc_raised),
+            info: contribution.info,
+        })
+        .filter(|asset| !asset.amount.is_zero())
+        .collect())
+}
+
+/// The amount of `info` held back for settlement refunds not yet claimed.
+fn get_reserved_excess(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    info: &AssetInfo,
+) -> StdResult<Uint128> {
+    let raised = match TOTAL_RAISED_BY_ASSET.may_load(deps.storage, info.key())? {
+        Some(raised) => raised.amount,
+        None => return Ok(Uint128::zero()),
+    };
+    let reserved = if let Some(clearing_price) = config.clearing_price(state.total_usdc_raised) {
+        let bids = AUCTION_BID_TOTALS
+            .may_load(deps.storage, info.key())?
+            .unwrap_or_default();
+        raised.saturating_sub(bids.settled_amount(clearing_price))
+    } else if is_oversubscribed(config, state) {
+        let excess = state.total_usdc_raised - config.hard_cap;
+        raised.multiply_ratio(excess, state.total_usdc_raised)
+    } else {
+        return Ok(Uint128::zero());
+    };
+    let refunded = EXCESS_REFUNDED_TOTALS
+        .may_load(deps.storage, info.key())?
+        .unwrap_or_default();
+    Ok(reserved.saturating_sub(refunded))
+}
+
+/// Splits the allocation of `user` into vested, claimed and locked parts at the current block
+/// time. Nothing vests until the sale has succeeded.
+fn get_claimable(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    user: &Addr,
+    env: &Env,
+) -> StdResult<ClaimableResponse> {
+    let now = env.block.time.seconds();
+    let total = get_eho_allocation(deps, config, state, user)?;
+    let claimed = CLAIMED.may_load(deps.storage, user)?.unwrap_or_default();
+    let vested = if settled_status(config, state, now) != SaleStatus::Succeeded {
+        Uint128::zero()
+    } else {
+        match &config.vesting {
+            Some(vesting) => vesting.vested_amount(total, now),
+            None => total,
+        }
+    };
+    Ok(ClaimableResponse {
+        total,
+        vested,
+        claimed,
+        locked: total - vested,
+        claimable: vested.saturating_sub(claimed),
+    })
+}
+
+fn clear_round_contributions(deps: DepsMut, config: &Config, user: &Addr) {
+    for index in 0..config.sale_rounds().len() {
+        ROUND_CONTRIBUTIONS.remove(deps.storage, (index as u32, user));
     }
-    Ok(total_value)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -473,6 +2310,6 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
         MigrateMsg::MinimalUpgrade {} => {}
     }
     Ok(Response::new()
-        .add_attribute("action", "migrate_to_open_access")
+        .add_attribute("action", "migrate")
         .add_attribute("new_version", CONTRACT_VERSION))
 }
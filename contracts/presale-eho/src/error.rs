@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,12 +6,27 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Unauthorized: Caller is not the contract admin")]
+    #[error("Unauthorized: Caller does not hold the required role")]
     Unauthorized {},
 
+    #[error("No admin transfer has been proposed")]
+    NoAdminProposal {},
+
+    #[error("The admin proposal has expired")]
+    AdminProposalExpired {},
+
     #[error("Sale is not active")]
     SaleNotActive {},
 
+    #[error("Sale is not funded: {deposited} of the required {required} EHO deposited")]
+    SaleNotFunded {
+        required: Uint128,
+        deposited: Uint128,
+    },
+
+    #[error("The presale must be the EHO token's minter to mint on claim")]
+    NotEhoMinter {},
+
     #[error("Sale has not started yet")]
     SaleNotStarted {},
 
@@ -20,13 +35,22 @@ pub enum ContractError {
 
     #[error("Sale is still active, cannot claim or refund yet")]
     SaleIsStillActive {},
-    
+
     #[error("Sale is not in a state that can be ended (must be Active)")]
     SaleCannotBeEnded {},
 
+    #[error("Cap for payment asset '{denom}' exceeded, {remaining} of USDC value remains")]
+    DenomCapExceeded { denom: String, remaining: Uint128 },
+
     #[error("Hard cap has been reached")]
     HardCapReached {},
 
+    #[error("No sale round is open at this time")]
+    NoActiveRound {},
+
+    #[error("Cap for round '{round}' has been reached")]
+    RoundCapReached { round: String },
+
     #[error("Soft cap was not reached, sale failed. Cannot claim tokens.")]
     SoftCapNotReached {},
 
@@ -39,6 +63,33 @@ pub enum ContractError {
     #[error("Address is not on the whitelist")]
     NotInWhitelist {},
 
+    #[error("Buyers cannot refer themselves")]
+    InvalidReferrer {},
+
+    #[error("Whitelist proof does not match the registered Merkle root")]
+    InvalidMerkleProof {},
+
+    #[error("No Merkle root has been registered")]
+    MerkleRootNotSet {},
+
+    #[error("No KYC signer has been registered")]
+    KycSignerNotSet {},
+
+    #[error("KYC voucher signature is invalid")]
+    InvalidVoucherSignature {},
+
+    #[error("KYC voucher has expired")]
+    VoucherExpired {},
+
+    #[error("KYC voucher nonce {nonce} has already been used")]
+    VoucherAlreadyUsed { nonce: u64 },
+
+    #[error("The claim deadline has passed")]
+    ClaimDeadlinePassed {},
+
+    #[error("The claim deadline has not passed yet")]
+    ClaimDeadlineNotReached {},
+
     #[error("Caller has nothing to claim")]
     NothingToClaim {},
 
@@ -51,15 +102,18 @@ pub enum ContractError {
     #[error("Contribution exceeds maximum limit per user")]
     UserCapExceeded {},
 
+    #[error("Contributions must total at least {min}")]
+    BelowMinimumContribution { min: Uint128 },
+
     #[error("Invalid payment: Must be a single, accepted coin type")]
     InvalidPayment {},
 
     #[error("Payment denom '{denom}' is not an accepted payment type")]
     UnacceptedPaymentDenom { denom: String },
-    
+
     #[error("Configuration error: {details}")]
     ConfigError { details: String },
-    
+
     #[error("Contract is currently paused")]
     Paused {},
 
@@ -68,4 +122,16 @@ pub enum ContractError {
 
     #[error("No tokens available to reclaim")]
     NoTokensToReclaim {},
-}
\ No newline at end of file
+
+    #[error("Oracle price for '{denom}' is stale")]
+    OraclePriceStale { denom: String },
+
+    #[error(
+        "Oracle price {price} for '{denom}' deviates too far from the reference rate {reference}"
+    )]
+    OraclePriceDeviation {
+        denom: String,
+        price: Uint128,
+        reference: Uint128,
+    },
+}
@@ -1,6 +1,8 @@
 pub mod contract;
 mod error;
 pub mod msg;
+pub mod oracle;
 pub mod state;
+mod whitelist;
 
 pub use crate::error::ContractError;
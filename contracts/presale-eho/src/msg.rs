@@ -1,11 +1,76 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Binary, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+use crate::state::{
+    Asset, ConfigChange, Contribution, Distribution, OracleConfig, ReferralConfig, ReferrerStats,
+    Role, Round, SaleMode, TierBonusConfig, VestingSchedule, DEFAULT_PAYMENT_DECIMALS,
+};
 
 // Helper struct for instantiation
 #[cw_serde]
 pub struct Rate {
     pub denom: String,
     pub rate: Uint128, // The value of 1 full token in USDC, with 6 decimals
+    /// The number of decimals of the denom, e.g. 18 for assets bridged from EVM chains.
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    /// The most USDC-equivalent value that may be raised in this asset, if limited.
+    /// Left unset in `UpdateRates`, any existing cap is kept.
+    #[serde(default)]
+    pub cap: Option<Uint128>,
+}
+
+fn default_decimals() -> u8 {
+    DEFAULT_PAYMENT_DECIMALS
+}
+
+/// An address to whitelist with its individual limits.
+#[cw_serde]
+pub struct WhitelistAddition {
+    pub address: String,
+    /// The most USDC-equivalent value the address may contribute. Defaults to the sale-wide cap.
+    pub cap: Option<Uint128>,
+    /// The least USDC-equivalent value the address must contribute.
+    pub min: Option<Uint128>,
+}
+
+/// An off-chain KYC approval for the buyer, signed by the registered KYC signer over
+/// `whitelist::voucher_hash` of the presale address, the buyer and these terms.
+#[cw_serde]
+pub struct KycVoucher {
+    /// The contribution cap granted to the buyer, if any.
+    pub cap: Option<Uint128>,
+    /// The timestamp (in seconds) after which the voucher can no longer be redeemed.
+    pub expiry: u64,
+    /// A number used once per buyer, so a voucher cannot be replayed.
+    pub nonce: u64,
+    /// The 64-byte secp256k1 signature.
+    pub signature: Binary,
+}
+
+/// Sale parameters to change with `UpdateConfig`. Fields left as `None` are kept.
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigUpdate {
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    pub soft_cap: Option<Uint128>,
+    pub hard_cap: Option<Uint128>,
+    pub eho_price: Option<Uint128>,
+    pub max_contribution_per_user: Option<Uint128>,
+    pub claim_deadline: Option<u64>,
+    /// Can also be changed after the sale has started.
+    pub treasury: Option<String>,
+}
+
+/// A proof of inclusion in the Merkle whitelist registered by the admin.
+#[cw_serde]
+pub struct WhitelistProof {
+    /// The contribution cap committed to in the leaf, if the leaf carries one.
+    pub cap: Option<Uint128>,
+    /// Hex-encoded sibling hashes, ordered from the leaf up to the root.
+    pub proof: Vec<String>,
 }
 
 #[cw_serde]
@@ -13,39 +78,154 @@ pub struct InstantiateMsg {
     pub admin: String,
     pub eho_token_address: String,
     pub accepted_rates: Vec<Rate>,
+    /// Rates for accepted CW20 payment tokens, with the token contract address as `denom`.
+    pub accepted_cw20_rates: Vec<Rate>,
     pub start_time: u64,
     pub end_time: u64,
     pub soft_cap: Uint128,
     pub hard_cap: Uint128,
     pub max_contribution_per_user: Uint128,
     pub eho_price: Uint128,
+    /// Optional sale rounds. Leave empty to run a single round over the whole sale window.
+    pub rounds: Vec<Round>,
+    /// How purchases are priced and settled.
+    pub sale_mode: SaleMode,
+    /// Optional vesting of claimed allocations. Leave unset to release everything on claim.
+    pub vesting: Option<VestingSchedule>,
+    /// Optional price oracle for valuing payments. Leave unset to use the static rates only.
+    pub oracle: Option<OracleConfig>,
+    /// Optional referral program. Leave unset to track referrals without paying bonuses.
+    pub referral: Option<ReferralConfig>,
+    /// Optional volume-based bonus tiers.
+    pub tier_bonus: Option<TierBonusConfig>,
+    /// Optional end of the whitelist-only window, after which the sale is open to anyone.
+    pub whitelist_end_time: Option<u64>,
+    /// Optional deadline for claims and refunds, after which unclaimed balances can be swept.
+    pub claim_deadline: Option<u64>,
+    /// Whether claims transfer deposited EHO or mint it.
+    pub distribution: Distribution,
+    /// Optional recipient of withdrawn funds and unsold or unclaimed EHO. Defaults to the admin.
+    pub treasury: Option<String>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Allows a whitelisted user to buy tokens by sending accepted native tokens with the message.
-    Buy {},
+    /// Addresses on the Merkle whitelist include their proof on their first purchase.
+    /// An optional `referrer` is credited with the purchase under the referral program.
+    /// A `kyc_voucher` signed by the KYC provider admits the buyer instead of a whitelist entry.
+    Buy {
+        whitelist_proof: Option<WhitelistProof>,
+        referrer: Option<String>,
+        kyc_voucher: Option<KycVoucher>,
+    },
+    /// Entry point for CW20 `Send`s. Accepted CW20 payment tokens buy with `ReceiveMsg::Buy`.
+    Receive(Cw20ReceiveMsg),
     /// Allows a user to claim their EHO tokens after a successful sale.
+    /// Under a vesting schedule, each call releases whatever has vested since the last claim.
+    /// In an oversubscribed `FairLaunch` sale, the first claim also refunds the unused contribution,
+    /// and in a Dutch auction what was paid above the clearing price.
     ClaimTokens {},
     /// Allows a user to request a refund if the sale failed.
     RequestRefund {},
+    /// Claims on behalf of up to `limit` contributors and referral bonus holders, continuing
+    /// from where the previous batch stopped. Callable by anyone; tokens always go to the holders.
+    DistributeBatch {
+        limit: u32,
+    },
+    /// Refunds up to `limit` contributors of a failed or cancelled sale, continuing from where
+    /// the previous batch stopped. Callable by anyone; funds always go to the contributors.
+    RefundBatch {
+        limit: u32,
+    },
 
     // --- Admin Functions ---
     EndSale {},
+    /// Aborts a pending or active sale. Every contributor can then request a refund,
+    /// raised funds can no longer be withdrawn and the full EHO deposit can be reclaimed.
+    CancelSale {
+        reason: String,
+    },
+    /// Whitelists addresses, each with an optional individual cap and minimum.
     AddToWhitelist {
-        addresses: Vec<String>,
+        entries: Vec<WhitelistAddition>,
     },
     RemoveFromWhitelist {
         addresses: Vec<String>,
     },
+    /// Changes the sale parameters before the sale starts. Once it has started, only the treasury
+    /// can change and the end time be pushed back, once and by at most `MAX_END_TIME_EXTENSION`,
+    /// taking the end of the final round with it. Every change is logged.
+    UpdateConfig(ConfigUpdate),
+    /// Registers (or replaces) the hex-encoded root of the Merkle whitelist.
+    UpdateMerkleRoot {
+        merkle_root: String,
+    },
+    /// Registers (or replaces) the secp256k1 public key of the KYC voucher signer,
+    /// or stops accepting vouchers when `None`.
+    UpdateKycSigner {
+        pubkey: Option<Binary>,
+    },
+    /// Returns the EHO not owed to contributors to the treasury: everything after a failed or
+    /// cancelled sale, otherwise the balance in excess of the allocations not claimed yet.
     ReclaimUnsoldTokens {},
+    /// Once the claim deadline has passed, sends every EHO and payment asset balance left
+    /// in the contract to the treasury.
+    SweepUnclaimed {},
     WithdrawFunds {},
-    UpdateAdmin {
+    /// Proposes `new_admin` as the next admin, replacing any pending proposal.
+    /// The transfer only takes effect once the proposed address accepts it, before `expiry` if set.
+    ProposeAdmin {
         new_admin: String,
+        expiry: Option<u64>,
+    },
+    /// Accepts a pending admin proposal. Only callable by the proposed address.
+    AcceptAdmin {},
+    /// Withdraws the pending admin proposal.
+    CancelAdminProposal {},
+    /// Permanently gives up admin rights, leaving the contract without an admin.
+    /// Granted roles are kept.
+    RenounceAdmin {},
+    /// Grants `role` to `address`. Only callable by the admin.
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    /// Revokes `role` from `address`. Only callable by the admin.
+    RevokeRole {
+        address: String,
+        role: Role,
     },
     UpdatePause {
         pause: bool,
     },
+    /// Sets the rates of new or existing payment assets and stops accepting the `remove`d ones.
+    /// Contributions already made keep the value they were bought at.
+    /// The caps of the assets in `remove_caps` are lifted before the new rates apply.
+    UpdateRates {
+        rates: Vec<Rate>,
+        cw20_rates: Vec<Rate>,
+        remove: Vec<String>,
+        #[serde(default)]
+        remove_caps: Vec<String>,
+    },
+    /// Sets or removes the price oracle used to value payments.
+    UpdateOracle {
+        oracle: Option<OracleConfig>,
+    },
+}
+
+/// Messages embedded in the `msg` field of a CW20 `Send` to the presale.
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Buys EHO with the CW20 tokens sent, like `ExecuteMsg::Buy` does with native funds.
+    Buy {
+        whitelist_proof: Option<WhitelistProof>,
+        referrer: Option<String>,
+        kyc_voucher: Option<KycVoucher>,
+    },
+    /// Deposits the EHO sent for the sale. Only accepted from the EHO token.
+    Fund {},
 }
 
 #[cw_serde]
@@ -58,21 +238,215 @@ pub enum QueryMsg {
     #[returns(crate::state::State)]
     State {},
     /// Returns the exchange rates for all accepted tokens.
-    #[returns(Vec<Rate>)]
+    #[returns(Vec<AcceptedRateResponse>)]
     AcceptedRates {},
-    /// Checks if a given address is whitelisted.
-    #[returns(bool)]
+    /// Checks if a given address is whitelisted, and with which individual limits.
+    #[returns(IsWhitelistedResponse)]
     IsWhitelisted { address: String },
-    /// Returns the total USDC-equivalent value a user has contributed.
+    /// Returns the hex-encoded Merkle whitelist root, if one is registered.
+    #[returns(Option<String>)]
+    MerkleRoot {},
+    /// Lists the changes made with `UpdateConfig`, oldest first.
+    #[returns(ConfigChangesResponse)]
+    ConfigChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the current admin, if it has not been renounced.
+    #[returns(cw_controllers::AdminResponse)]
+    Admin {},
+    /// Returns the pending admin proposal, if any.
+    #[returns(Option<crate::state::PendingAdmin>)]
+    PendingAdmin {},
+    /// Returns the owner and lists the roles granted to other addresses, ordered by address.
+    #[returns(RolesResponse)]
+    Roles {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the public key of the KYC voucher signer, if one is registered.
+    #[returns(Option<Binary>)]
+    KycSigner {},
+    /// Checks a Merkle whitelist proof for `address` against the registered root
+    /// without buying.
+    #[returns(bool)]
+    VerifyWhitelistProof {
+        address: String,
+        whitelist_proof: WhitelistProof,
+    },
+    /// Returns the round open at the current block time, if any.
+    #[returns(Option<RoundResponse>)]
+    CurrentRound {},
+    /// Returns every sale round along with the value raised in it so far.
+    #[returns(Vec<RoundResponse>)]
+    Rounds {},
+    /// Returns the total USDC-equivalent value a user has contributed, valued at purchase time.
     #[returns(Uint128)]
     TotalContributionOf { address: String },
-    /// Returns the specific coins a user has contributed.
-    #[returns(Vec<cosmwasm_std::Coin>)]
+    /// Returns the specific assets a user has contributed, with the value locked in for each.
+    #[returns(Vec<crate::state::Contribution>)]
     ContributionsOf { address: String },
     /// Calculates and returns the amount of EHO a user is entitled to claim
     /// based on their current contribution. Returns 0 if they haven't contributed.
-    #[returns(cosmwasm_std::Uint128)]
+    /// While a `FairLaunch` sale is live this is the projection at the current totals.
+    #[returns(AllocationResponse)]
     EhoAllocationOf { address: String },
+    /// Reports how much of a user's allocation has vested, been claimed and remains locked
+    /// at the current block time.
+    #[returns(ClaimableResponse)]
+    ClaimableOf { address: String },
+    /// Returns the price of 1 EHO at the current block time: the active round price, or the
+    /// auction price of a Dutch auction (its clearing price once settled).
+    #[returns(Uint128)]
+    CurrentPrice {},
+    /// Lists contributors and the assets they contributed, ordered by address.
+    #[returns(AllContributionsResponse)]
+    AllContributions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the addresses on the admin-managed whitelist (including proven Merkle addresses),
+    /// ordered by address.
+    #[returns(AllWhitelistedResponse)]
+    AllWhitelisted {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns how much EHO the sale needs, how much has been deposited and how much
+    /// is currently owed to contributors.
+    #[returns(FundingResponse)]
+    Funding {},
+    /// Returns participation counters and the raise per asset.
+    #[returns(SaleStatsResponse)]
+    SaleStats {},
+    /// Lists the volume referred by each referrer and the bonus they earned, ordered by address.
+    #[returns(ReferralStatsResponse)]
+    ReferralStats {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct RoundResponse {
+    pub index: u32,
+    pub round: Round,
+    /// The USDC-equivalent value raised in this round so far.
+    pub total_raised: Uint128,
+}
+
+#[cw_serde]
+pub struct AllocationResponse {
+    /// The EHO the user is entitled to, including referral and tier bonuses.
+    pub eho_allocation: Uint128,
+    /// The part of `eho_allocation` earned through the referral program.
+    pub referral_bonus: Uint128,
+    /// The part of `eho_allocation` granted by the volume-based bonus tiers.
+    pub tier_bonus: Uint128,
+    /// The part of the user's contribution to be returned because the sale is oversubscribed,
+    /// or because it was paid above a Dutch auction's clearing price.
+    pub refund: Vec<Asset>,
+}
+
+#[cw_serde]
+pub struct IsWhitelistedResponse {
+    pub whitelisted: bool,
+    /// The address's individual cap, if any.
+    pub cap: Option<Uint128>,
+    /// The address's individual minimum, if any.
+    pub min: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct ContributorInfo {
+    pub address: String,
+    pub contributions: Vec<Contribution>,
+}
+
+#[cw_serde]
+pub struct AllContributionsResponse {
+    pub contributors: Vec<ContributorInfo>,
+}
+
+#[cw_serde]
+pub struct AllWhitelistedResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct SaleStatsResponse {
+    pub contributor_count: u64,
+    pub claimed_count: u64,
+    pub refunded_count: u64,
+    pub total_usdc_raised: Uint128,
+    /// The amount raised in each accepted asset.
+    pub raised_by_asset: Vec<Asset>,
+    /// The USDC-equivalent value that can still be raised before the hard cap.
+    pub remaining_capacity: Uint128,
+}
+
+#[cw_serde]
+pub struct ReferrerInfo {
+    pub referrer: String,
+    pub stats: ReferrerStats,
+}
+
+#[cw_serde]
+pub struct ConfigChangesResponse {
+    pub changes: Vec<ConfigChange>,
+}
+
+#[cw_serde]
+pub struct AcceptedRateResponse {
+    pub denom: String,
+    pub rate: Uint128,
+    pub decimals: u8,
+    /// The most USDC-equivalent value that may be raised in this asset, if limited.
+    pub cap: Option<Uint128>,
+    /// The USDC-equivalent value that can still be raised in this asset, if limited.
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct FundingResponse {
+    /// The EHO the sale must be funded with before it can start.
+    pub required: Uint128,
+    /// The EHO deposited so far.
+    pub deposited: Uint128,
+    /// The EHO allocated to contributors and not claimed yet.
+    pub liability: Uint128,
+}
+
+#[cw_serde]
+pub struct RoleMember {
+    pub address: String,
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    /// The admin, who implicitly holds every role.
+    pub owner: Option<String>,
+    pub members: Vec<RoleMember>,
+}
+
+#[cw_serde]
+pub struct ReferralStatsResponse {
+    pub referrers: Vec<ReferrerInfo>,
+}
+
+#[cw_serde]
+pub struct ClaimableResponse {
+    /// The user's total EHO allocation.
+    pub total: Uint128,
+    /// The part of the allocation released so far.
+    pub vested: Uint128,
+    /// The amount the user has already claimed.
+    pub claimed: Uint128,
+    /// The part of the allocation that has not vested yet.
+    pub locked: Uint128,
+    /// The amount a `ClaimTokens` call would transfer right now.
+    pub claimable: Uint128,
 }
 
 #[cw_serde]
@@ -0,0 +1,68 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Deps, Uint128};
+
+use crate::error::ContractError;
+use crate::state::OracleConfig;
+
+/// The query interface the presale expects from a price oracle contract.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum OracleQueryMsg {
+    /// Returns the USD price of `denom`, a native denom or a CW20 contract address.
+    #[returns(PriceResponse)]
+    Price { denom: String },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    /// The USDC-equivalent value (with 6 decimals) of one whole token of the asset,
+    /// in the same unit as `Rate::rate`.
+    pub price: Uint128,
+    /// The timestamp (in seconds) of the last price update.
+    pub updated_at: u64,
+}
+
+/// Returns the rate to value a payment in `denom` at `now`, using the oracle price when it
+/// is fresh and falling back to `static_rate` when allowed. Fresh prices outside the
+/// configured band around `static_rate` are rejected.
+pub fn resolve_rate(
+    deps: Deps,
+    oracle: &OracleConfig,
+    denom: &str,
+    static_rate: Uint128,
+    now: u64,
+) -> Result<Uint128, ContractError> {
+    let response: Result<PriceResponse, _> = deps.querier.query_wasm_smart(
+        oracle.contract.clone(),
+        &OracleQueryMsg::Price {
+            denom: denom.to_string(),
+        },
+    );
+    let price = match response {
+        Ok(price)
+            if !price.price.is_zero()
+                && now.saturating_sub(price.updated_at) <= oracle.max_staleness =>
+        {
+            price.price
+        }
+        _ if oracle.fallback_to_static => return Ok(static_rate),
+        Ok(_) => {
+            return Err(ContractError::OraclePriceStale {
+                denom: denom.to_string(),
+            })
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(max_deviation) = oracle.max_deviation {
+        let deviation = price.abs_diff(static_rate);
+        if deviation > static_rate.mul_floor(max_deviation) {
+            return Err(ContractError::OraclePriceDeviation {
+                denom: denom.to_string(),
+                price,
+                reference: static_rate,
+            });
+        }
+    }
+    Ok(price)
+}
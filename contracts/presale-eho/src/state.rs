@@ -1,16 +1,22 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
+use cw_controllers::Admin;
 use cw_storage_plus::{Item, Map};
 
-// --- CONFIGURATION (Set once at instantiation) ---
+// --- CONFIGURATION (Set at instantiation; rates can be updated by the admin) ---
 #[cw_serde]
 pub struct Config {
-    /// The admin of the contract, who can manage the whitelist, pause, and withdraw funds.
-    pub admin: Addr,
+    /// The address receiving withdrawn funds, reclaimed EHO and swept balances.
+    pub treasury: Addr,
     /// The address of the EHO token contract to be distributed.
     pub eho_token_address: Addr,
     /// The list of accepted native token denoms for payment (e.g., Noble USDC, Axelar USDC, ATOM, OSMO).
+    /// Kept in sync with `EXCHANGE_RATES` by `UpdateRates`.
     pub accepted_payment_denoms: Vec<String>,
+    /// The CW20 token contracts accepted for payment through `Receive` (e.g., CW20-wrapped USDC).
+    pub accepted_cw20_tokens: Vec<Addr>,
     /// The timestamp (in seconds) when the sale starts.
     pub start_time: u64,
     /// The timestamp (in seconds) when the sale ends.
@@ -23,13 +29,351 @@ pub struct Config {
     pub max_contribution_per_user: Uint128,
     /// The price of 1 EHO in USDC-equivalent value (with 6 decimals). E.g., $0.01 = 10000
     pub eho_price: Uint128,
+    /// The sale rounds (e.g., seed -> private -> public), ordered by time.
+    /// When empty, the sale runs as a single whitelisted round spanning the whole window.
+    pub rounds: Vec<Round>,
+    /// How purchases are priced and settled.
+    pub sale_mode: SaleMode,
+    /// How sold EHO reaches contributors.
+    pub distribution: Distribution,
+    /// How claimed allocations are released. When `None`, the full allocation is claimable
+    /// as soon as the sale succeeds.
+    pub vesting: Option<VestingSchedule>,
+    /// An optional price oracle used to value payments at buy time instead of the static rates.
+    pub oracle: Option<OracleConfig>,
+    /// The bonuses paid for referred purchases. When `None`, referrals are tracked without bonuses.
+    pub referral: Option<ReferralConfig>,
+    /// Bonus EHO for large cumulative contributions, paid from a capped pool.
+    pub tier_bonus: Option<TierBonusConfig>,
+    /// The timestamp (in seconds) from which anyone can buy, lifting the whitelist requirement
+    /// of every round. When `None`, each round's `whitelist_required` applies throughout.
+    pub whitelist_end_time: Option<u64>,
+    /// The timestamp (in seconds) after which claims and refunds close and whatever is left
+    /// unclaimed can be swept to the treasury. When `None`, claims stay open indefinitely.
+    pub claim_deadline: Option<u64>,
 }
 pub const CONFIG: Item<Config> = Item::new("config");
 
-/// A map from an accepted payment denom to its value in USDC (with 6 decimals).
+impl Config {
+    /// Returns the configured rounds, or the single implicit round built from the
+    /// top-level sale parameters when none were configured.
+    pub fn sale_rounds(&self) -> Vec<Round> {
+        if !self.rounds.is_empty() {
+            return self.rounds.clone();
+        }
+        vec![Round {
+            name: "public".to_string(),
+            start_time: self.start_time,
+            end_time: self.end_time,
+            eho_price: self.eho_price,
+            round_cap: self.hard_cap,
+            max_contribution_per_user: self.max_contribution_per_user,
+            whitelist_required: true,
+        }]
+    }
+
+    /// Returns the index and parameters of the round open at `now`, if any.
+    pub fn active_round(&self, now: u64) -> Option<(u32, Round)> {
+        self.sale_rounds()
+            .into_iter()
+            .enumerate()
+            .find(|(_, round)| round.start_time <= now && now < round.end_time)
+            .map(|(index, round)| (index as u32, round))
+    }
+
+    /// Returns who may buy at `now`: whitelisted addresses only, or anyone once the whitelist
+    /// window has ended or in rounds that don't require it. Before the sale starts, the policy
+    /// of the first round is reported.
+    pub fn access_mode(&self, now: u64) -> AccessMode {
+        if self
+            .whitelist_end_time
+            .is_some_and(|whitelist_end_time| now >= whitelist_end_time)
+        {
+            return AccessMode::Public;
+        }
+        let round = match self.active_round(now) {
+            Some((_, round)) => Some(round),
+            None if now < self.start_time => self.sale_rounds().into_iter().next(),
+            None => None,
+        };
+        match round {
+            Some(round) if round.whitelist_required => AccessMode::WhitelistOnly,
+            _ => AccessMode::Public,
+        }
+    }
+
+    /// Returns the Dutch auction price of 1 EHO at `now`, or `None` for other sale modes.
+    pub fn auction_price(&self, now: u64) -> Option<Uint128> {
+        let SaleMode::DutchAuction {
+            start_price,
+            floor_price,
+            step_seconds,
+            ..
+        } = &self.sale_mode
+        else {
+            return None;
+        };
+        let mut elapsed = now.clamp(self.start_time, self.end_time) - self.start_time;
+        if *step_seconds > 0 {
+            elapsed -= elapsed % step_seconds;
+        }
+        let decline =
+            (*start_price - *floor_price).multiply_ratio(elapsed, self.end_time - self.start_time);
+        Some(*start_price - decline)
+    }
+
+    /// Returns the value of the auctioned supply at `now`, the raise at which a Dutch auction
+    /// sells out, or `None` for other sale modes.
+    pub fn auction_capacity(&self, now: u64) -> Option<Uint128> {
+        let SaleMode::DutchAuction { supply, .. } = &self.sale_mode else {
+            return None;
+        };
+        let price = self.auction_price(now)?;
+        Some(supply.multiply_ratio(price, Uint128::new(1_000_000)))
+    }
+
+    /// Returns the EHO the presale must hold to honour every allocation it can sell: the hard cap
+    /// at the lowest round price (or the auction supply), the most referral bonuses that can be
+    /// earned on it and the tier bonus pool. Nothing when EHO is minted on claim.
+    pub fn required_eho(&self) -> Uint128 {
+        if self.distribution == Distribution::Mint {
+            return Uint128::zero();
+        }
+        let sold = match &self.sale_mode {
+            SaleMode::DutchAuction { supply, .. } => *supply,
+            _ => {
+                let lowest_price = self
+                    .sale_rounds()
+                    .iter()
+                    .map(|round| round.eho_price)
+                    .min()
+                    .unwrap_or(self.eho_price);
+                self.hard_cap
+                    .multiply_ratio(Uint128::new(1_000_000), lowest_price)
+            }
+        };
+        let referral_bonuses = self.referral.as_ref().map_or(Uint128::zero(), |referral| {
+            sold.mul_ceil(referral.buyer_bonus + referral.referrer_bonus)
+        });
+        let tier_bonuses = self
+            .tier_bonus
+            .as_ref()
+            .map_or(Uint128::zero(), |tier_bonus| tier_bonus.pool);
+        sold + referral_bonuses + tier_bonuses
+    }
+
+    /// Returns the price every participant of a Dutch auction pays once `total_raised` is
+    /// final: the raise spread over the supply, but never below the floor price.
+    /// Rounded up so the allocations never exceed the supply.
+    pub fn clearing_price(&self, total_raised: Uint128) -> Option<Uint128> {
+        let SaleMode::DutchAuction {
+            floor_price,
+            supply,
+            ..
+        } = &self.sale_mode
+        else {
+            return None;
+        };
+        let mut price = total_raised.multiply_ratio(Uint128::new(1_000_000), *supply);
+        if price.multiply_ratio(*supply, Uint128::new(1_000_000)) < total_raised {
+            price += Uint128::one();
+        }
+        Some(price.max(*floor_price))
+    }
+}
+
+/// Who may buy.
+#[cw_serde]
+pub enum AccessMode {
+    WhitelistOnly,
+    Public,
+}
+
+/// Where the EHO paid out to contributors comes from.
+#[cw_serde]
+#[derive(Default)]
+pub enum Distribution {
+    /// Transferred from the balance deposited for the sale.
+    #[default]
+    Transfer,
+    /// Minted on claim, so unsold EHO never exists. The presale must be the EHO token's minter
+    /// before the sale starts, and takes no deposit.
+    Mint,
+}
+
+/// How purchases are priced and settled.
+#[cw_serde]
+#[derive(Default)]
+pub enum SaleMode {
+    /// Each round sells at its fixed price until the hard cap is reached.
+    #[default]
+    FixedPrice,
+    /// Contributions above the hard cap are accepted during the sale window. At settlement
+    /// every participant receives EHO pro-rata and the unused part of their contribution back.
+    FairLaunch,
+    /// The price of `supply` EHO declines from `start_price` to `floor_price` over the sale
+    /// window, in steps of `step_seconds` (or linearly when zero), replacing the round prices.
+    /// The auction sells out once the raise covers the supply at the current price, and every
+    /// participant settles at the same clearing price: each bid keeps the EHO it bought at its
+    /// own price and the part of it paid above the clearing price is refunded.
+    DutchAuction {
+        start_price: Uint128,
+        floor_price: Uint128,
+        step_seconds: u64,
+        supply: Uint128,
+    },
+}
+
+/// Release schedule for presale allocations: a share unlocked at the token generation
+/// event (TGE), then linear vesting of the remainder after a cliff.
+#[cw_serde]
+pub struct VestingSchedule {
+    /// The timestamp (in seconds) of the TGE, from which the schedule is measured.
+    pub tge_time: u64,
+    /// The share of each allocation released at TGE, e.g. 0.2 for 20%.
+    pub tge_unlock: Decimal,
+    /// Seconds after TGE before linear vesting of the remainder begins.
+    pub cliff_seconds: u64,
+    /// Seconds over which the remainder vests linearly once the cliff has passed.
+    pub vesting_seconds: u64,
+}
+
+impl VestingSchedule {
+    /// Returns when the whole allocation has vested, or `None` if that overflows.
+    pub fn end_time(&self) -> Option<u64> {
+        self.tge_time
+            .checked_add(self.cliff_seconds)?
+            .checked_add(self.vesting_seconds)
+    }
+
+    /// Returns how much of `total` has vested at `now`.
+    pub fn vested_amount(&self, total: Uint128, now: u64) -> Uint128 {
+        if now < self.tge_time {
+            return Uint128::zero();
+        }
+        let unlocked_at_tge = total.mul_floor(self.tge_unlock);
+        let vesting_start = self.tge_time + self.cliff_seconds;
+        if now < vesting_start {
+            return unlocked_at_tge;
+        }
+        let elapsed = now - vesting_start;
+        if elapsed >= self.vesting_seconds {
+            return total;
+        }
+        unlocked_at_tge + (total - unlocked_at_tge).multiply_ratio(elapsed, self.vesting_seconds)
+    }
+}
+
+/// Where and how payment prices are read from an external oracle.
+/// The static rates in `EXCHANGE_RATES` remain the reference band and the fallback.
+#[cw_serde]
+pub struct OracleConfig {
+    /// The oracle contract, answering `oracle::OracleQueryMsg::Price`.
+    pub contract: Addr,
+    /// The maximum age (in seconds) of a price before it is considered stale.
+    pub max_staleness: u64,
+    /// The maximum relative deviation from the static rate, e.g. 0.1 for 10%.
+    /// Purchases are rejected when the oracle price is outside this band.
+    pub max_deviation: Option<Decimal>,
+    /// Whether to value payments at the static rate when the oracle price is stale or unavailable,
+    /// instead of rejecting the purchase.
+    pub fallback_to_static: bool,
+}
+
+/// Volume-based bonus tiers, e.g. +5% EHO from $1k and +10% from $10k contributed.
+#[cw_serde]
+pub struct TierBonusConfig {
+    /// The tiers, ordered by increasing threshold and bonus.
+    pub tiers: Vec<BonusTier>,
+    /// The most bonus EHO the sale will grant across all contributors.
+    pub pool: Uint128,
+}
+
+#[cw_serde]
+pub struct BonusTier {
+    /// The cumulative USDC-equivalent contribution (with 6 decimals) from which the tier applies.
+    pub threshold: Uint128,
+    /// The bonus on all EHO bought by a contributor in this tier, e.g. 0.05 for 5%.
+    pub bonus: Decimal,
+}
+
+impl TierBonusConfig {
+    /// The bonus of the highest tier reached with `contributed`, if any.
+    pub fn bonus_for(&self, contributed: Uint128) -> Decimal {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| contributed >= tier.threshold)
+            .map(|tier| tier.bonus)
+            .unwrap_or_default()
+    }
+}
+
+/// Bonus EHO granted on referred purchases, as a share of the EHO bought.
+#[cw_serde]
+pub struct ReferralConfig {
+    /// The bonus granted to the buyer, e.g. 0.05 for 5%.
+    pub buyer_bonus: Decimal,
+    /// The bonus granted to the referrer, e.g. 0.1 for 10%.
+    pub referrer_bonus: Decimal,
+}
+
+/// A single phase of the sale with its own window, price and limits.
+#[cw_serde]
+pub struct Round {
+    pub name: String,
+    /// The timestamp (in seconds) when the round opens.
+    pub start_time: u64,
+    /// The timestamp (in seconds) when the round closes.
+    pub end_time: u64,
+    /// The price of 1 EHO in this round, in USDC-equivalent value (with 6 decimals).
+    pub eho_price: Uint128,
+    /// The maximum USDC-equivalent value the round will accept.
+    pub round_cap: Uint128,
+    /// The maximum USDC-equivalent value any single user may contribute in this round.
+    pub max_contribution_per_user: Uint128,
+    /// Whether only whitelisted addresses may buy in this round.
+    pub whitelist_required: bool,
+}
+
+/// A map from an accepted payment denom (or CW20 contract address) to its value in USDC (with 6 decimals).
 /// e.g., "ibc/..." -> "1000000" for USDC, "ibc/..." -> "7000000" for ATOM at $7.00
 pub const EXCHANGE_RATES: Map<&str, Uint128> = Map::new("exchange_rates");
 
+/// The decimals of each payment asset in `EXCHANGE_RATES`, used to normalise amounts
+/// to whole tokens. Assets without an entry use `DEFAULT_PAYMENT_DECIMALS`.
+pub const PAYMENT_DECIMALS: Map<&str, u8> = Map::new("payment_decimals");
+pub const DEFAULT_PAYMENT_DECIMALS: u8 = 6;
+/// The most decimals a payment asset may have (as on EVM chains).
+pub const MAX_PAYMENT_DECIMALS: u8 = 18;
+/// The most USDC-equivalent value that may be raised in each capped payment asset,
+/// keyed like `EXCHANGE_RATES`.
+pub const DENOM_CAPS: Map<&str, Uint128> = Map::new("denom_caps");
+/// The USDC-equivalent value raised so far in each payment asset, keyed like `EXCHANGE_RATES`.
+pub const RAISED_VALUE_BY_DENOM: Map<&str, Uint128> = Map::new("raised_value_by_denom");
+
+/// The furthest `UpdateConfig` may push back the end time of a sale that has started (7 days).
+pub const MAX_END_TIME_EXTENSION: u64 = 7 * 24 * 60 * 60;
+
+/// One parameter changed by `UpdateConfig`, with its values rendered as strings.
+#[cw_serde]
+pub struct ParamChange {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// An entry of the audit log of `UpdateConfig` calls.
+#[cw_serde]
+pub struct ConfigChange {
+    pub id: u64,
+    pub changed_by: Addr,
+    /// The block time (in seconds) of the change.
+    pub changed_at: u64,
+    pub changes: Vec<ParamChange>,
+}
+pub const CONFIG_CHANGES: Map<u64, ConfigChange> = Map::new("config_changes");
+
 // --- STATE (Changes during the sale) ---
 #[cw_serde]
 pub struct State {
@@ -39,6 +383,22 @@ pub struct State {
     pub sale_status: SaleStatus,
     /// A flag to halt buy functionality in case of emergencies.
     pub paused: bool,
+    /// Who may buy. The `State` query reports the policy in force at the current block time.
+    pub access_mode: AccessMode,
+    /// The price of 1 EHO every participant pays, recorded when a Dutch auction succeeds.
+    pub clearing_price: Option<Uint128>,
+    /// The number of addresses that have contributed.
+    pub contributor_count: u64,
+    /// The number of contributors that have claimed EHO.
+    pub claimed_count: u64,
+    /// The total EHO claimed by contributors so far.
+    pub total_eho_claimed: Uint128,
+    /// The EHO deposited for the sale through CW20 `Send`s from the EHO token.
+    pub tokens_for_sale: Uint128,
+    /// The number of contributors refunded after a failed sale.
+    pub refunded_count: u64,
+    /// Whether the end time has been extended since the sale started.
+    pub end_time_extended: bool,
 }
 pub const STATE: Item<State> = Item::new("state");
 
@@ -47,13 +407,203 @@ pub enum SaleStatus {
     Pending,
     Active,
     Succeeded,
-    Failed, // Refunds enabled
+    Failed,    // Refunds enabled
+    Cancelled, // Aborted by the admin; refunds enabled
+}
+
+/// A payment asset accepted by the presale.
+#[cw_serde]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract_addr: Addr },
+}
+
+impl AssetInfo {
+    /// The key the asset's rate is stored under in `EXCHANGE_RATES`:
+    /// the denom for native tokens and the contract address for CW20 tokens.
+    pub fn key(&self) -> &str {
+        match self {
+            AssetInfo::Native { denom } => denom,
+            AssetInfo::Cw20 { contract_addr } => contract_addr.as_str(),
+        }
+    }
+}
+
+/// An amount of a native or CW20 payment asset.
+#[cw_serde]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.info.key())
+    }
+}
+
+/// The total amount raised in each payment asset, keyed by `AssetInfo::key`.
+pub const TOTAL_RAISED_BY_ASSET: Map<&str, Asset> = Map::new("total_raised_by_asset");
+
+/// An asset a user contributed, with its USDC-equivalent value locked in at purchase time
+/// so later rate changes cannot alter the user's allocation or cap usage.
+#[cw_serde]
+pub struct Contribution {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+    /// The USDC-equivalent value (with 6 decimals) of `amount` at the rates used when buying.
+    pub usdc_value: Uint128,
+}
+
+impl Contribution {
+    pub fn asset(&self) -> Asset {
+        Asset {
+            info: self.info.clone(),
+            amount: self.amount,
+        }
+    }
 }
 
 // --- USER DATA ---
-/// A map from a user's address to a vector of the actual coins they have contributed.
+/// A map from a user's address to the actual assets (native coins and CW20 tokens) they have contributed.
 /// This is crucial for accurate refunds of multiple asset types.
-pub const CONTRIBUTIONS: Map<&Addr, Vec<Coin>> = Map::new("contributions");
+pub const CONTRIBUTIONS: Map<&Addr, Vec<Contribution>> = Map::new("contributions");
+
+/// The referral activity of a referrer.
+#[cw_serde]
+#[derive(Default)]
+pub struct ReferrerStats {
+    /// The USDC-equivalent value of the purchases made with this referrer.
+    pub referred_volume: Uint128,
+    /// The number of purchases made with this referrer.
+    pub referred_purchases: u64,
+    /// The bonus EHO earned by this referrer.
+    pub bonus_eho: Uint128,
+}
+pub const REFERRER_STATS: Map<&Addr, ReferrerStats> = Map::new("referrer_stats");
+
+/// The bonus EHO each address earned through the referral program, as a buyer or as a referrer,
+/// at the round price of the purchases it was earned on.
+pub const REFERRAL_BONUSES: Map<&Addr, Uint128> = Map::new("referral_bonuses");
+/// The sum of all `REFERRAL_BONUSES`.
+pub const REFERRAL_BONUS_TOTAL: Item<Uint128> = Item::new("referral_bonus_total");
+
+/// The tier bonus EHO reserved for each contributor from the bonus pool.
+pub const TIER_BONUSES: Map<&Addr, Uint128> = Map::new("tier_bonuses");
+/// The total tier bonus EHO reserved so far, never more than `TierBonusConfig::pool`.
+pub const TIER_BONUS_RESERVED: Item<Uint128> = Item::new("tier_bonus_reserved");
+
+/// The USDC-equivalent value each user contributed per round, recorded at purchase time.
+/// Keyed by (round index, user).
+pub const ROUND_CONTRIBUTIONS: Map<(u32, &Addr), Uint128> = Map::new("round_contributions");
+
+/// The total USDC-equivalent value raised in each round, keyed by round index.
+pub const ROUND_TOTALS: Map<u32, Uint128> = Map::new("round_totals");
+
+/// What a contributor's Dutch auction bids bought with one payment asset.
+#[cw_serde]
+#[derive(Default)]
+pub struct AuctionBid {
+    /// The EHO bought, each bid at its own price.
+    pub eho: Uint128,
+    /// The amount paid divided by the price of each bid (with 6 decimals), which gives the part
+    /// of the payment kept at any clearing price.
+    pub amount_per_price: Uint128,
+}
+
+impl AuctionBid {
+    /// Returns the part of the payment kept when the auction clears at `clearing_price`,
+    /// rounded up so the refunds never exceed what was paid.
+    pub fn settled_amount(&self, clearing_price: Uint128) -> Uint128 {
+        self.amount_per_price
+            .mul_ceil(Decimal::from_ratio(clearing_price, Uint128::new(1_000_000)))
+    }
+}
+
+/// The Dutch auction bids of each user per payment asset, keyed by (user, `AssetInfo::key`).
+pub const AUCTION_BIDS: Map<(&Addr, &str), AuctionBid> = Map::new("auction_bids");
+/// The sum of all `AUCTION_BIDS` per payment asset, keyed by `AssetInfo::key`.
+pub const AUCTION_BID_TOTALS: Map<&str, AuctionBid> = Map::new("auction_bid_totals");
+
+/// The amount of EHO each user has already claimed.
+pub const CLAIMED: Map<&Addr, Uint128> = Map::new("claimed");
+
+/// Users who have received the refund of their unused contribution from an oversubscribed
+/// `FairLaunch` sale, or of what they paid above a Dutch auction's clearing price.
+pub const EXCESS_REFUNDED: Map<&Addr, bool> = Map::new("excess_refunded");
+
+/// The last contributor settled by `DistributeBatch`, while a pass over the contributors is underway.
+pub const DISTRIBUTION_CURSOR: Item<Addr> = Item::new("distribution_cursor");
+/// The last contributor settled by `RefundBatch`, while a pass over the contributors is underway.
+pub const REFUND_CURSOR: Item<Addr> = Item::new("refund_cursor");
+
+/// The total settlement refunds paid out so far per payment asset, keyed by `AssetInfo::key`.
+pub const EXCESS_REFUNDED_TOTALS: Map<&str, Uint128> = Map::new("excess_refunded_totals");
+
+/// The individual limits of a whitelisted address. Unset limits fall back to the sale-wide ones.
+#[cw_serde]
+#[derive(Default)]
+pub struct WhitelistEntry {
+    /// The most USDC-equivalent value the address may contribute, replacing
+    /// `max_contribution_per_user` and the per-round limits.
+    pub cap: Option<Uint128>,
+    /// The least USDC-equivalent value the address must have contributed after each purchase.
+    pub min: Option<Uint128>,
+}
+
+/// A map of whitelisted addresses to their individual limits.
+/// Addresses admitted through a Merkle proof are cached here, with the cap committed to
+/// in their leaf, after their first purchase.
+pub const WHITELIST: Map<&Addr, WhitelistEntry> = Map::new("whitelist");
+
+/// A permission that can be granted to other addresses, so day-to-day operations do not
+/// need the owner's key.
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Transfers ownership, grants roles and updates rates and the oracle. Held only by the admin.
+    Owner,
+    /// Pauses and unpauses purchases and ends the sale.
+    Pauser,
+    /// Manages the whitelist, the Merkle root and the KYC signer.
+    WhitelistManager,
+    /// Withdraws raised funds and reclaims unsold EHO, always to the treasury.
+    Treasurer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Owner => write!(f, "owner"),
+            Role::Pauser => write!(f, "pauser"),
+            Role::WhitelistManager => write!(f, "whitelist_manager"),
+            Role::Treasurer => write!(f, "treasurer"),
+        }
+    }
+}
+
+/// The admin (owner) of the contract, who holds every role and alone can change the sale's
+/// economics and grant roles. Unset once the admin has been renounced.
+pub const ADMIN: Admin = Admin::new("admin");
+
+/// The roles granted to each address besides the admin.
+pub const ROLES: Map<&Addr, Vec<Role>> = Map::new("roles");
+
+/// An admin transfer proposed by the current admin, awaiting acceptance by `address`.
+#[cw_serde]
+pub struct PendingAdmin {
+    /// The proposed new admin.
+    pub address: Addr,
+    /// The timestamp (in seconds) after which the proposal can no longer be accepted, if any.
+    pub expiry: Option<u64>,
+}
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+
+/// The hex-encoded root of the Merkle whitelist registered by the admin, if any.
+pub const MERKLE_ROOT: Item<String> = Item::new("merkle_root");
+
+/// The secp256k1 public key of the KYC provider whose signed vouchers admit buyers, if any.
+pub const KYC_SIGNER: Item<Binary> = Item::new("kyc_signer");
 
-/// A map of whitelisted addresses. The bool value must be `true`.
-pub const WHITELIST: Map<&Addr, bool> = Map::new("whitelist");
+/// KYC voucher nonces already redeemed, per buyer.
+pub const USED_VOUCHER_NONCES: Map<(&Addr, u64), bool> = Map::new("used_voucher_nonces");
@@ -0,0 +1,56 @@
+use cosmwasm_std::{HexBinary, StdError, StdResult, Uint128};
+use sha2::{Digest, Sha256};
+
+/// Decodes a hex-encoded Merkle root and checks it is a 32-byte SHA-256 digest.
+pub fn parse_merkle_root(root: &str) -> StdResult<[u8; 32]> {
+    let bytes = HexBinary::from_hex(root)?;
+    bytes
+        .to_array::<32>()
+        .map_err(|_| StdError::generic_err("Merkle root must be a 32-byte hex string"))
+}
+
+/// The leaf committed to the whitelist tree for `address`.
+/// Leaves without a cap hash the bare address, capped leaves hash `"{address}:{cap}"`.
+pub fn leaf_hash(address: &str, cap: Option<Uint128>) -> [u8; 32] {
+    let preimage = match cap {
+        Some(cap) => format!("{}:{}", address, cap),
+        None => address.to_string(),
+    };
+    Sha256::digest(preimage.as_bytes()).into()
+}
+
+/// The message hash a KYC voucher signature is made over, committing to the presale contract,
+/// the buyer and the voucher terms: `sha256("{contract}:{buyer}:{cap}:{expiry}:{nonce}")`,
+/// with an empty `cap` for uncapped vouchers.
+pub fn voucher_hash(
+    contract: &str,
+    buyer: &str,
+    cap: Option<Uint128>,
+    expiry: u64,
+    nonce: u64,
+) -> [u8; 32] {
+    let cap = cap.map(|cap| cap.to_string()).unwrap_or_default();
+    let preimage = format!("{}:{}:{}:{}:{}", contract, buyer, cap, expiry, nonce);
+    Sha256::digest(preimage.as_bytes()).into()
+}
+
+/// Walks `proof` from `leaf` up to the root, hashing each pair in sorted order,
+/// and compares the result against `root`.
+pub fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[String]) -> StdResult<bool> {
+    let mut computed = leaf;
+    for sibling in proof {
+        let sibling = HexBinary::from_hex(sibling)?
+            .to_array::<32>()
+            .map_err(|_| StdError::generic_err("Proof elements must be 32-byte hex strings"))?;
+        let (first, second) = if computed <= sibling {
+            (computed, sibling)
+        } else {
+            (sibling, computed)
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(first);
+        hasher.update(second);
+        computed = hasher.finalize().into();
+    }
+    Ok(&computed == root)
+}
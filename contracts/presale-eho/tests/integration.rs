@@ -1,13 +1,34 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coin, Addr, Coin, Empty, Uint128};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, to_json_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
+    Response, StdResult, Uint128,
+};
 use cw20::Cw20ExecuteMsg;
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_controllers::AdminResponse;
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Map;
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use sha2::{Digest, Sha256};
 
 use cw_multi_test::AppBuilder;
 
-use presale_eho::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, Rate};
-use presale_eho::state::{Config, SaleStatus, State};
+use presale_eho::msg::{
+    AcceptedRateResponse, AllContributionsResponse, AllWhitelistedResponse, AllocationResponse,
+    ClaimableResponse, ConfigChangesResponse, ConfigUpdate, ExecuteMsg, FundingResponse,
+    InstantiateMsg, IsWhitelistedResponse, KycVoucher, QueryMsg, Rate, ReceiveMsg,
+    ReferralStatsResponse, RoleMember, RolesResponse, RoundResponse, SaleStatsResponse,
+    WhitelistAddition, WhitelistProof,
+};
+use presale_eho::oracle::{OracleQueryMsg, PriceResponse};
+use presale_eho::state::{
+    AccessMode, Asset, AssetInfo, BonusTier, Config, Contribution, Distribution, OracleConfig,
+    ParamChange, PendingAdmin, ReferralConfig, ReferrerStats, Role, Round, SaleMode, SaleStatus,
+    State, TierBonusConfig, VestingSchedule, MAX_END_TIME_EXTENSION,
+};
 use presale_eho::ContractError;
 
 // --- IBC Denoms for Realistic Testing ---
@@ -15,6 +36,8 @@ const NOBLE_USDC: &str = "ibc/B559A80D62249C8AA07A380E2A2BEA6E5CA9A6F079C912C3A9
 const AXELAR_USDC: &str = "ibc/F082B65C88E4B6D5EF1DB243CDA1D331D002759E938A0F5CD3FFDC5D53B3E349";
 const ATOM: &str = "ibc/C4CFF46FD6DE35CA4CF4CE031E643C8FDC9BA4B99AE598E9B0ED98FE3A2319F9";
 const OSMO: &str = "ibc/376222D6D9DAE23092E29740E56B758580935A6D77C24C2ABD57A6A78A1F3955";
+// Axelar-bridged WETH, with 18 decimals
+const WETH: &str = "ibc/A585C2D15DCD3B010849B453A2CFCB5E213208A5AB665691792684C26274304D";
 
 // --- Helper Contracts ---
 fn eho_cw20_contract() -> Box<dyn Contract<Empty>> {
@@ -35,16 +58,97 @@ fn presale_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+// --- Mock Price Oracle ---
+const MOCK_PRICES: Map<&str, PriceResponse> = Map::new("prices");
+
+#[cw_serde]
+enum MockOracleExecuteMsg {
+    SetPrice {
+        denom: String,
+        price: Uint128,
+        updated_at: u64,
+    },
+}
+
+fn mock_oracle_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |deps: DepsMut,
+         _env: Env,
+         _info: MessageInfo,
+         msg: MockOracleExecuteMsg|
+         -> StdResult<Response> {
+            let MockOracleExecuteMsg::SetPrice {
+                denom,
+                price,
+                updated_at,
+            } = msg;
+            MOCK_PRICES.save(deps.storage, &denom, &PriceResponse { price, updated_at })?;
+            Ok(Response::new())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |deps: Deps, _env: Env, msg: OracleQueryMsg| -> StdResult<Binary> {
+            let OracleQueryMsg::Price { denom } = msg;
+            to_json_binary(&MOCK_PRICES.load(deps.storage, &denom)?)
+        },
+    );
+    Box::new(contract)
+}
+
+/// Whitelist entries for `addresses` without individual limits.
+fn uncapped_entries(addresses: &[&Addr]) -> Vec<WhitelistAddition> {
+    addresses
+        .iter()
+        .map(|address| WhitelistAddition {
+            address: address.to_string(),
+            cap: None,
+            min: None,
+        })
+        .collect()
+}
+
+/// Mints 400M EHO to the admin and deposits it into the presale for the sale.
+fn fund_presale(app: &mut App, admin: &Addr, eho_addr: &Addr, presale_addr: &Addr) {
+    app.execute_contract(
+        admin.clone(),
+        eho_addr.clone(),
+        &Cw20ExecuteMsg::Mint {
+            recipient: admin.to_string(),
+            amount: Uint128::new(400_000_000_000_000), // 400M EHO
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        admin.clone(),
+        eho_addr.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: presale_addr.to_string(),
+            amount: Uint128::new(400_000_000_000_000),
+            msg: to_json_binary(&ReceiveMsg::Fund {}).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+}
+
 // --- Test Setup Helper ---
 struct TestSetup {
     app: App,
     presale_addr: Addr,
     eho_addr: Addr,
+    cw20_usdc_addr: Addr,
     admin: Addr,
     alice: Addr,
     bob: Addr,
 }
 fn setup() -> TestSetup {
+    setup_with(|_| {})
+}
+
+/// Like `setup`, but lets a test adjust the presale `InstantiateMsg` before instantiation.
+fn setup_with(configure: impl FnOnce(&mut InstantiateMsg)) -> TestSetup {
     // Define user addresses with the "cosmwasm" prefix
     let admin = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3sxfqx5vwjh");
     let alice = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3sy3q8mmchv");
@@ -83,8 +187,30 @@ fn setup() -> TestSetup {
         )
         .unwrap();
 
+    // Instantiate a CW20-wrapped USDC accepted as payment, with a balance for Alice
+    let cw20_usdc_addr = app
+        .instantiate_contract(
+            eho_code_id,
+            admin.clone(),
+            &cw20_eho::msg::InstantiateMsg {
+                name: "Wrapped USDC".to_string(),
+                symbol: "WUSDC".to_string(),
+                decimals: 6,
+                initial_balances: vec![cw20::Cw20Coin {
+                    address: alice.to_string(),
+                    amount: Uint128::new(50_000_000_000),
+                }],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "WUSDC",
+            None,
+        )
+        .unwrap();
+
     // Instantiate Presale contract with realistic parameters
-    let presale_instantiate_msg = InstantiateMsg {
+    let mut presale_instantiate_msg = InstantiateMsg {
         admin: admin.to_string(),
         eho_token_address: eho_addr.to_string(),
         eho_price: Uint128::new(10_000), // $0.01 per EHO
@@ -92,26 +218,51 @@ fn setup() -> TestSetup {
             Rate {
                 denom: NOBLE_USDC.to_string(),
                 rate: Uint128::new(1_000_000),
+                decimals: 6,
+                cap: None,
             },
             Rate {
                 denom: AXELAR_USDC.to_string(),
                 rate: Uint128::new(1_000_000),
+                decimals: 6,
+                cap: None,
             },
             Rate {
                 denom: ATOM.to_string(),
                 rate: Uint128::new(7_000_000),
+                decimals: 6,
+                cap: None,
             },
             Rate {
                 denom: OSMO.to_string(),
                 rate: Uint128::new(550_000),
+                decimals: 6,
+                cap: None,
             },
         ],
+        accepted_cw20_rates: vec![Rate {
+            denom: cw20_usdc_addr.to_string(),
+            rate: Uint128::new(1_000_000),
+            decimals: 6,
+            cap: None,
+        }],
         start_time: app.block_info().time.seconds() + 100,
         end_time: app.block_info().time.seconds() + 200,
         soft_cap: Uint128::new(100_000_000_000), // 100k USDC
         hard_cap: Uint128::new(500_000_000_000), // 500k USDC
         max_contribution_per_user: Uint128::new(200_000_000_000), // Increased for test
+        rounds: vec![],
+        sale_mode: SaleMode::FixedPrice,
+        vesting: None,
+        oracle: None,
+        referral: None,
+        tier_bonus: None,
+        whitelist_end_time: None,
+        claim_deadline: None,
+        distribution: Distribution::Transfer,
+        treasury: None,
     };
+    configure(&mut presale_instantiate_msg);
     let presale_addr = app
         .instantiate_contract(
             presale_code_id,
@@ -123,17 +274,10 @@ fn setup() -> TestSetup {
         )
         .unwrap();
 
-    // Fund the presale contract with EHO for distribution
-    app.execute_contract(
-        admin.clone(),
-        eho_addr.clone(),
-        &Cw20ExecuteMsg::Mint {
-            recipient: presale_addr.to_string(),
-            amount: Uint128::new(400_000_000_000_000), // 400M EHO
-        },
-        &[],
-    )
-    .unwrap();
+    // Fund the presale contract with EHO for distribution, unless it mints on claim
+    if presale_instantiate_msg.distribution == Distribution::Transfer {
+        fund_presale(&mut app, &admin, &eho_addr, &presale_addr);
+    }
 
     // Mint native funds for users
     app.sudo(cw_multi_test::SudoMsg::Bank(
@@ -155,12 +299,61 @@ fn setup() -> TestSetup {
         app,
         presale_addr,
         eho_addr,
+        cw20_usdc_addr,
         admin,
         alice,
         bob,
     }
 }
 
+// --- Merkle Whitelist Helpers ---
+fn merkle_leaf(address: &Addr, cap: Option<Uint128>) -> [u8; 32] {
+    let preimage = match cap {
+        Some(cap) => format!("{}:{}", address, cap),
+        None => address.to_string(),
+    };
+    Sha256::digest(preimage.as_bytes()).into()
+}
+
+fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+/// Builds a sorted-pair Merkle tree and returns the hex root along with the hex proof
+/// for every leaf, in the order the leaves were given.
+fn merkle_tree(leaves: &[[u8; 32]]) -> (String, Vec<Vec<String>>) {
+    let mut proofs = vec![vec![]; leaves.len()];
+    let mut positions: Vec<usize> = (0..leaves.len()).collect();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let mut next = vec![];
+        for pair in level.chunks(2) {
+            next.push(if pair.len() == 2 {
+                hash_pair(pair[0], pair[1])
+            } else {
+                pair[0]
+            });
+        }
+        for (leaf, pos) in positions.iter_mut().enumerate() {
+            let sibling = *pos ^ 1;
+            if sibling < level.len() {
+                proofs[leaf]
+                    .push(cosmwasm_std::HexBinary::from(level[sibling].as_slice()).to_hex());
+            }
+            *pos /= 2;
+        }
+        level = next;
+    }
+    (
+        cosmwasm_std::HexBinary::from(level[0].as_slice()).to_hex(),
+        proofs,
+    )
+}
+
 #[test]
 fn test_instantiation_and_config() {
     let setup = setup();
@@ -172,7 +365,11 @@ fn test_instantiation_and_config() {
         .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::Config {})
         .unwrap();
 
-    assert_eq!(config.admin, setup.admin);
+    let admin: AdminResponse = app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::Admin {})
+        .unwrap();
+    assert_eq!(admin.admin, Some(setup.admin.to_string()));
     assert_eq!(config.eho_token_address, setup.eho_addr);
     assert_eq!(config.soft_cap, Uint128::new(100_000_000_000));
     assert_eq!(config.hard_cap, Uint128::new(500_000_000_000));
@@ -203,7 +400,7 @@ fn test_buy_logic_and_failures() {
             setup.admin.clone(),
             setup.presale_addr.clone(),
             &ExecuteMsg::AddToWhitelist {
-                addresses: vec![setup.alice.to_string()],
+                entries: uncapped_entries(&[&setup.alice]),
             },
             &[],
         )
@@ -215,7 +412,11 @@ fn test_buy_logic_and_failures() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(1_000_000_000, NOBLE_USDC)],
         )
         .unwrap_err();
@@ -232,7 +433,11 @@ fn test_buy_logic_and_failures() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(5_000_000_000, NOBLE_USDC)], // 5k USDC
         )
         .unwrap();
@@ -251,7 +456,11 @@ fn test_buy_logic_and_failures() {
         .execute_contract(
             setup.bob.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(1_000_000_000, OSMO)],
         )
         .unwrap_err();
@@ -272,7 +481,11 @@ fn test_buy_logic_and_failures() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(1_000, "untrn")],
         )
         .unwrap_err();
@@ -284,7 +497,11 @@ fn test_buy_logic_and_failures() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(196_000_000_000, NOBLE_USDC)], // To exceed 200k
         )
         .unwrap_err();
@@ -302,7 +519,7 @@ fn test_successful_sale_flow() {
             setup.admin.clone(),
             setup.presale_addr.clone(),
             &ExecuteMsg::AddToWhitelist {
-                addresses: vec![setup.alice.to_string(), setup.bob.to_string()],
+                entries: uncapped_entries(&[&setup.alice, &setup.bob]),
             },
             &[],
         )
@@ -319,7 +536,11 @@ fn test_successful_sale_flow() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(14_285_714_286, ATOM)],
         )
         .unwrap();
@@ -330,7 +551,11 @@ fn test_successful_sale_flow() {
         .execute_contract(
             setup.bob.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(2_000_000_000, OSMO)],
         )
         .unwrap();
@@ -438,7 +663,7 @@ fn test_failed_sale_flow() {
             setup.admin.clone(),
             setup.presale_addr.clone(),
             &ExecuteMsg::AddToWhitelist {
-                addresses: vec![setup.alice.to_string()],
+                entries: uncapped_entries(&[&setup.alice]),
             },
             &[],
         )
@@ -455,7 +680,11 @@ fn test_failed_sale_flow() {
         .execute_contract(
             setup.alice.clone(),
             setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
             &[coin(5_000_000_000, NOBLE_USDC)],
         )
         .unwrap();
@@ -520,91 +749,4470 @@ fn test_failed_sale_flow() {
 }
 
 #[test]
-fn test_admin_controls() {
+fn test_cancelled_sale_flow() {
     let mut setup = setup();
-    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    let (admin, alice, bob, presale_addr, eho_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, funds: Coin| {
+        app.execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+    };
+    buy(&mut setup.app, coin(1_000_000_000, NOBLE_USDC)).unwrap();
+    buy(&mut setup.app, coin(100_000_000, ATOM)).unwrap();
 
-    // PAUSE
+    // Only the owner can cancel
+    let cancel = ExecuteMsg::CancelSale {
+        reason: "price feed compromised".to_string(),
+    };
+    let err = setup
+        .app
+        .execute_contract(bob.clone(), presale_addr.clone(), &cancel, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let res = setup
+        .app
+        .execute_contract(admin.clone(), presale_addr.clone(), &cancel, &[])
+        .unwrap();
+    let wasm = res.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+    assert!(wasm
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "reason" && attr.value == "price feed compromised"));
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.sale_status, SaleStatus::Cancelled);
+
+    // A cancelled sale takes no purchases, cannot be withdrawn from and cannot be cancelled again
+    let err = buy(&mut setup.app, coin(1_000_000, NOBLE_USDC)).unwrap_err();
+    assert_eq!(ContractError::SaleNotActive {}, err.downcast().unwrap());
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::WithdrawFunds {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::SaleNotSucceeded {}, err.downcast().unwrap());
+    let err = setup
+        .app
+        .execute_contract(admin.clone(), presale_addr.clone(), &cancel, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::SaleHasEnded {}, err.downcast().unwrap());
+
+    // Alice is refunded every asset before the window closes
     setup
         .app
         .execute_contract(
-            setup.admin.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::UpdatePause { pause: true },
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::RequestRefund {},
             &[],
         )
         .unwrap();
+    let usdc = setup
+        .app
+        .wrap()
+        .query_balance(alice.clone(), NOBLE_USDC)
+        .unwrap();
+    assert_eq!(usdc.amount, Uint128::new(300_000_000_000));
+    let atom = setup.app.wrap().query_balance(alice.clone(), ATOM).unwrap();
+    assert_eq!(atom.amount, Uint128::new(15_000_000_000));
 
-    // FAIL: Alice tries to buy while paused
+    // The whole EHO deposit goes back to the owner
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ReclaimUnsoldTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &admin),
+        Uint128::new(400_000_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &presale_addr),
+        Uint128::zero()
+    );
+}
+
+/// Whitelists Alice and Bob and has them buy 1,000 USDC and 1,000 OSMO ($550) worth of EHO.
+fn two_contributor_sale(setup: &mut TestSetup) {
+    let (alice, bob) = (setup.alice.clone(), setup.bob.clone());
     setup
         .app
         .execute_contract(
             setup.admin.clone(),
             setup.presale_addr.clone(),
             &ExecuteMsg::AddToWhitelist {
-                addresses: vec![setup.alice.to_string()],
+                entries: uncapped_entries(&[&alice, &bob]),
             },
             &[],
         )
         .unwrap();
-    setup
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    for (buyer, funds) in [
+        (&alice, coin(1_000_000_000, NOBLE_USDC)),
+        (&bob, coin(1_000_000_000, OSMO)),
+    ] {
+        setup
+            .app
+            .execute_contract(
+                buyer.clone(),
+                setup.presale_addr.clone(),
+                &ExecuteMsg::Buy {
+                    whitelist_proof: None,
+                    referrer: None,
+                    kyc_voucher: None,
+                },
+                &[funds],
+            )
+            .unwrap();
+    }
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+}
+
+fn wasm_attribute(res: &AppResponse, key: &str) -> String {
+    res.events
+        .iter()
+        .filter(|ev| ev.ty == "wasm")
+        .flat_map(|ev| &ev.attributes)
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+        .unwrap()
+}
+
+#[test]
+fn test_batch_distribution() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    two_contributor_sale(&mut setup);
+    let (alice, bob, presale_addr, eho_addr) = (
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+    );
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    let distribute = |app: &mut App| {
+        app.execute_contract(
+            charlie.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::DistributeBatch { limit: 1 },
+            &[],
+        )
+        .unwrap()
+    };
+
+    // Anyone can push claims, one contributor per batch here
+    let res = distribute(&mut setup.app);
+    assert_eq!(wasm_attribute(&res, "processed"), "1");
+    assert_eq!(wasm_attribute(&res, "finished"), "false");
+    let res = distribute(&mut setup.app);
+    assert_eq!(wasm_attribute(&res, "finished"), "true");
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &alice),
+        Uint128::new(100_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &bob),
+        Uint128::new(55_000_000_000)
+    );
+    let stats: SaleStatsResponse = setup
         .app
-        .update_block(|b| b.time = b.time.plus_seconds(101));
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::SaleStats {})
+        .unwrap();
+    assert_eq!(stats.claimed_count, 2);
+
+    // Nothing is left to claim, and a new pass pays out nothing
     let err = setup
         .app
         .execute_contract(
-            setup.alice.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
-            &[coin(1_000, NOBLE_USDC)],
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
         )
         .unwrap_err();
-    assert_eq!(ContractError::Paused {}, err.downcast().unwrap());
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+    let res = distribute(&mut setup.app);
+    assert_eq!(wasm_attribute(&res, "recipients"), "0");
+
+    // Refund batches only run after a failed sale
+    let err = setup
+        .app
+        .execute_contract(
+            charlie.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::RefundBatch { limit: 10 },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::SaleNotSucceeded {}, err.downcast().unwrap());
+}
 
-    // UPDATE ADMIN
+#[test]
+fn test_batch_distribution_pays_referrers() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.referral = Some(ReferralConfig {
+            buyer_bonus: Decimal::percent(5),
+            referrer_bonus: Decimal::percent(10),
+        });
+    });
+    let (admin, alice, presale_addr, eho_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+    );
+    // Charlie refers Alice but never buys
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
     setup
         .app
         .execute_contract(
-            setup.admin.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::UpdateAdmin {
-                new_admin: charlie.to_string(),
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: Some(charlie.to_string()),
+                kyc_voucher: None,
             },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+
+    // One batch pays both the buyer and the referrer
+    let res = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::DistributeBatch { limit: 10 },
             &[],
         )
         .unwrap();
+    assert_eq!(wasm_attribute(&res, "recipients"), "2");
+    assert_eq!(wasm_attribute(&res, "finished"), "true");
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &alice),
+        Uint128::new(105_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &charlie),
+        Uint128::new(10_000_000_000)
+    );
+}
 
-    // FAIL: Old admin tries to use admin powers
+#[test]
+fn test_batch_refunds() {
+    let mut setup = setup();
+    two_contributor_sale(&mut setup);
+    let (alice, bob, presale_addr) = (
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
     let err = setup
         .app
         .execute_contract(
-            setup.admin.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::UpdatePause { pause: false },
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::DistributeBatch { limit: 10 },
             &[],
         )
         .unwrap_err();
-    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    assert_eq!(ContractError::SoftCapNotReached {}, err.downcast().unwrap());
 
-    // SUCCESS: New admin (Charlie) unpauses the contract
-    setup
+    let res = setup
         .app
         .execute_contract(
-            charlie.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::UpdatePause { pause: false },
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::RefundBatch { limit: 10 },
             &[],
         )
         .unwrap();
-
-    // SUCCESS: Alice can now buy
-    setup
+    assert_eq!(wasm_attribute(&res, "refunded"), "2");
+    assert_eq!(wasm_attribute(&res, "finished"), "true");
+    let usdc = setup
+        .app
+        .wrap()
+        .query_balance(alice.clone(), NOBLE_USDC)
+        .unwrap();
+    assert_eq!(usdc.amount, Uint128::new(300_000_000_000));
+    let osmo = setup.app.wrap().query_balance(bob.clone(), OSMO).unwrap();
+    assert_eq!(osmo.amount, Uint128::new(2_000_000_000));
+    let err = setup
         .app
         .execute_contract(
-            setup.alice.clone(),
-            setup.presale_addr.clone(),
-            &ExecuteMsg::Buy {},
-            &[coin(1_000, NOBLE_USDC)],
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::RequestRefund {},
+            &[],
         )
+        .unwrap_err();
+    assert_eq!(ContractError::NothingToRefund {}, err.downcast().unwrap());
+    let stats: SaleStatsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::SaleStats {})
         .unwrap();
-}
\ No newline at end of file
+    assert_eq!(stats.refunded_count, 2);
+}
+
+#[test]
+fn test_claim_deadline_and_sweep() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.claim_deadline = Some(msg.end_time + 1_000);
+    });
+    two_contributor_sale(&mut setup);
+    let (admin, alice, bob, presale_addr, eho_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+    );
+    let execute = |app: &mut App, sender: &Addr, msg: &ExecuteMsg| {
+        app.execute_contract(sender.clone(), presale_addr.clone(), msg, &[])
+    };
+    execute(&mut setup.app, &alice, &ExecuteMsg::ClaimTokens {}).unwrap();
+
+    // Before the deadline, only the EHO not owed to Bob can be reclaimed
+    execute(&mut setup.app, &admin, &ExecuteMsg::ReclaimUnsoldTokens {}).unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &admin),
+        Uint128::new(400_000_000_000_000 - 155_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &presale_addr),
+        Uint128::new(55_000_000_000)
+    );
+    let err = execute(&mut setup.app, &admin, &ExecuteMsg::ReclaimUnsoldTokens {}).unwrap_err();
+    assert_eq!(ContractError::NoTokensToReclaim {}, err.downcast().unwrap());
+    let err = execute(&mut setup.app, &admin, &ExecuteMsg::SweepUnclaimed {}).unwrap_err();
+    assert_eq!(
+        ContractError::ClaimDeadlineNotReached {},
+        err.downcast().unwrap()
+    );
+
+    // After the deadline Bob can no longer claim, and everything left is swept
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(1_000);
+    });
+    let err = execute(&mut setup.app, &bob, &ExecuteMsg::ClaimTokens {}).unwrap_err();
+    assert_eq!(
+        ContractError::ClaimDeadlinePassed {},
+        err.downcast().unwrap()
+    );
+    let err = execute(&mut setup.app, &bob, &ExecuteMsg::SweepUnclaimed {}).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    execute(&mut setup.app, &admin, &ExecuteMsg::SweepUnclaimed {}).unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &admin),
+        Uint128::new(400_000_000_000_000 - 100_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &presale_addr),
+        Uint128::zero()
+    );
+    let usdc = setup
+        .app
+        .wrap()
+        .query_balance(admin.clone(), NOBLE_USDC)
+        .unwrap();
+    assert_eq!(usdc.amount, Uint128::new(1_000_000_000));
+    let osmo = setup.app.wrap().query_balance(admin.clone(), OSMO).unwrap();
+    assert_eq!(osmo.amount, Uint128::new(1_000_000_000));
+}
+
+#[test]
+fn test_sale_funding() {
+    // At $0.001 per EHO the 500k USDC hard cap needs 500M EHO, more than the 400M deposited
+    let mut setup = setup_with(|msg| {
+        msg.eho_price = Uint128::new(1_000);
+    });
+    let (admin, alice, presale_addr, eho_addr, cw20_usdc_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+        setup.cw20_usdc_addr.clone(),
+    );
+    let funding = |app: &App| -> FundingResponse {
+        app.wrap()
+            .query_wasm_smart(presale_addr.clone(), &QueryMsg::Funding {})
+            .unwrap()
+    };
+    assert_eq!(
+        funding(&setup.app),
+        FundingResponse {
+            required: Uint128::new(500_000_000_000_000),
+            deposited: Uint128::new(400_000_000_000_000),
+            liability: Uint128::zero(),
+        }
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App| {
+        app.execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+    };
+
+    // The sale does not start until it is fully funded
+    let err = buy(&mut setup.app).unwrap_err();
+    assert_eq!(
+        ContractError::SaleNotFunded {
+            required: Uint128::new(500_000_000_000_000),
+            deposited: Uint128::new(400_000_000_000_000),
+        },
+        err.downcast().unwrap()
+    );
+
+    // Only EHO counts as funding
+    let fund = to_json_binary(&ReceiveMsg::Fund {}).unwrap();
+    let err = setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            cw20_usdc_addr.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: presale_addr.to_string(),
+                amount: Uint128::new(1_000_000),
+                msg: fund.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidPayment {}, err.downcast().unwrap());
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            eho_addr.clone(),
+            &Cw20ExecuteMsg::Mint {
+                recipient: admin.to_string(),
+                amount: Uint128::new(100_000_000_000_000),
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            eho_addr.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: presale_addr.to_string(),
+                amount: Uint128::new(100_000_000_000_000),
+                msg: fund,
+            },
+            &[],
+        )
+        .unwrap();
+
+    // Once funded, purchases go through and build up the liability
+    buy(&mut setup.app).unwrap();
+    assert_eq!(
+        funding(&setup.app),
+        FundingResponse {
+            required: Uint128::new(500_000_000_000_000),
+            deposited: Uint128::new(500_000_000_000_000),
+            liability: Uint128::new(1_000_000_000_000),
+        }
+    );
+}
+
+#[test]
+fn test_mint_on_claim() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.distribution = Distribution::Mint;
+    });
+    let (admin, alice, bob, presale_addr, eho_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+        setup.eho_addr.clone(),
+    );
+    let total_supply = |app: &App| -> Uint128 {
+        let info: cw20::TokenInfoResponse = app
+            .wrap()
+            .query_wasm_smart(eho_addr.clone(), &cw20::Cw20QueryMsg::TokenInfo {})
+            .unwrap();
+        info.total_supply
+    };
+    let funding: FundingResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::Funding {})
+        .unwrap();
+    assert_eq!(funding.required, Uint128::zero());
+
+    // The sale cannot start until the presale is the EHO minter
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+    };
+    let err = buy(&mut setup.app, &alice, coin(1_000_000_000, NOBLE_USDC)).unwrap_err();
+    assert_eq!(ContractError::NotEhoMinter {}, err.downcast().unwrap());
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            eho_addr.clone(),
+            &Cw20ExecuteMsg::UpdateMinter {
+                new_minter: Some(presale_addr.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    buy(&mut setup.app, &alice, coin(1_000_000_000, NOBLE_USDC)).unwrap();
+    buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+
+    // Supply grows by exactly what each contributor bought
+    assert_eq!(total_supply(&setup.app), Uint128::zero());
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(total_supply(&setup.app), Uint128::new(100_000_000_000));
+    setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(total_supply(&setup.app), Uint128::new(155_000_000_000));
+    assert_eq!(
+        cw20_balance(&setup.app, &eho_addr, &bob),
+        Uint128::new(55_000_000_000)
+    );
+
+    // No unsold EHO exists to reclaim
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ReclaimUnsoldTokens {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NoTokensToReclaim {}, err.downcast().unwrap());
+}
+
+#[test]
+fn test_admin_controls() {
+    let mut setup = setup();
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+
+    // PAUSE
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::UpdatePause { pause: true },
+            &[],
+        )
+        .unwrap();
+
+    // FAIL: Alice tries to buy while paused
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+        .app
+        .update_block(|b| b.time = b.time.plus_seconds(101));
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Paused {}, err.downcast().unwrap());
+
+    // TRANSFER ADMIN: proposed by the admin, accepted by Charlie
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: charlie.to_string(),
+                expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            charlie.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        )
+        .unwrap();
+
+    // FAIL: Old admin tries to use admin powers
+    let err = setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::UpdatePause { pause: false },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // SUCCESS: New admin (Charlie) unpauses the contract
+    setup
+        .app
+        .execute_contract(
+            charlie.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::UpdatePause { pause: false },
+            &[],
+        )
+        .unwrap();
+
+    // SUCCESS: Alice can now buy
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000, NOBLE_USDC)],
+        )
+        .unwrap();
+}
+#[test]
+fn test_admin_transfer_and_renounce() {
+    let mut setup = setup();
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    let pending_admin = |app: &App| -> Option<PendingAdmin> {
+        app.wrap()
+            .query_wasm_smart(presale_addr.clone(), &QueryMsg::PendingAdmin {})
+            .unwrap()
+    };
+    let now = setup.app.block_info().time.seconds();
+
+    // Only the admin can propose, and the expiry must be in the future
+    let err = setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: alice.to_string(),
+                expiry: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: alice.to_string(),
+                expiry: Some(now),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::AdminProposalExpired {},
+        err.downcast().unwrap()
+    );
+
+    // A proposal to Alice leaves the admin in charge until she accepts
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: alice.to_string(),
+                expiry: Some(now + 50),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        pending_admin(&setup.app),
+        Some(PendingAdmin {
+            address: alice.clone(),
+            expiry: Some(now + 50),
+        })
+    );
+    let err = setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // Once it expires it can no longer be accepted
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(50);
+    });
+    let err = setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::AdminProposalExpired {},
+        err.downcast().unwrap()
+    );
+
+    // A cancelled proposal cannot be accepted either
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: bob.to_string(),
+                expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::CancelAdminProposal {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(pending_admin(&setup.app), None);
+    let err = setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NoAdminProposal {}, err.downcast().unwrap());
+
+    // Renouncing drops the admin and any pending proposal for good
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ProposeAdmin {
+                new_admin: bob.to_string(),
+                expiry: None,
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::RenounceAdmin {},
+            &[],
+        )
+        .unwrap();
+    let current: AdminResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::Admin {})
+        .unwrap();
+    assert_eq!(current.admin, None);
+    assert_eq!(pending_admin(&setup.app), None);
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdatePause { pause: true },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+}
+
+#[test]
+fn test_role_based_access() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    let execute = |app: &mut App, sender: &Addr, msg: &ExecuteMsg| {
+        app.execute_contract(sender.clone(), presale_addr.clone(), msg, &[])
+    };
+
+    // Only the owner grants roles, and ownership itself cannot be granted
+    let grant = |address: &Addr, role: Role| ExecuteMsg::GrantRole {
+        address: address.to_string(),
+        role,
+    };
+    let err = execute(&mut setup.app, &alice, &grant(&alice, Role::Treasurer)).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    execute(&mut setup.app, &admin, &grant(&alice, Role::Owner)).unwrap_err();
+    execute(
+        &mut setup.app,
+        &admin,
+        &grant(&alice, Role::WhitelistManager),
+    )
+    .unwrap();
+    execute(&mut setup.app, &admin, &grant(&alice, Role::Pauser)).unwrap();
+    execute(&mut setup.app, &admin, &grant(&bob, Role::Pauser)).unwrap();
+    execute(&mut setup.app, &admin, &grant(&charlie, Role::Treasurer)).unwrap();
+    let roles: RolesResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::Roles {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(roles.owner, Some(admin.to_string()));
+    let mut expected = vec![
+        RoleMember {
+            address: alice.to_string(),
+            roles: vec![Role::WhitelistManager, Role::Pauser],
+        },
+        RoleMember {
+            address: bob.to_string(),
+            roles: vec![Role::Pauser],
+        },
+        RoleMember {
+            address: charlie.to_string(),
+            roles: vec![Role::Treasurer],
+        },
+    ];
+    expected.sort_by(|a, b| a.address.cmp(&b.address));
+    assert_eq!(roles.members, expected);
+
+    // Revoking the last role removes the member
+    execute(
+        &mut setup.app,
+        &admin,
+        &ExecuteMsg::RevokeRole {
+            address: alice.to_string(),
+            role: Role::Pauser,
+        },
+    )
+    .unwrap();
+    let err = execute(
+        &mut setup.app,
+        &alice,
+        &ExecuteMsg::UpdatePause { pause: true },
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // The whitelist manager manages the whitelist but cannot touch funds or rates
+    execute(
+        &mut setup.app,
+        &alice,
+        &ExecuteMsg::AddToWhitelist {
+            entries: uncapped_entries(&[&alice]),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        &mut setup.app,
+        &alice,
+        &ExecuteMsg::UpdateRates {
+            rates: vec![],
+            cw20_rates: vec![],
+            remove: vec![OSMO.to_string()],
+            remove_caps: vec![],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // The pauser pauses, but cannot whitelist
+    execute(
+        &mut setup.app,
+        &bob,
+        &ExecuteMsg::UpdatePause { pause: true },
+    )
+    .unwrap();
+    let err = execute(
+        &mut setup.app,
+        &bob,
+        &ExecuteMsg::AddToWhitelist {
+            entries: uncapped_entries(&[&bob]),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    execute(
+        &mut setup.app,
+        &bob,
+        &ExecuteMsg::UpdatePause { pause: false },
+    )
+    .unwrap();
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+
+    // Only the treasurer (or the owner) withdraws, and the funds go to the treasury
+    let err = execute(&mut setup.app, &bob, &ExecuteMsg::WithdrawFunds {}).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    execute(&mut setup.app, &charlie, &ExecuteMsg::WithdrawFunds {}).unwrap();
+    for (address, expected) in [(&charlie, 0), (&admin, 2_000_000_000)] {
+        let balance = setup
+            .app
+            .wrap()
+            .query_balance(address.clone(), NOBLE_USDC)
+            .unwrap();
+        assert_eq!(balance.amount, Uint128::new(expected));
+    }
+}
+
+#[test]
+fn test_update_config() {
+    let mut setup = setup();
+    let (admin, alice, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.presale_addr.clone(),
+    );
+    let update = |app: &mut App, sender: &Addr, update: ConfigUpdate| {
+        app.execute_contract(
+            sender.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateConfig(update),
+            &[],
+        )
+    };
+    let config = |app: &App| -> Config {
+        app.wrap()
+            .query_wasm_smart(presale_addr.clone(), &QueryMsg::Config {})
+            .unwrap()
+    };
+    let original = config(&setup.app);
+
+    // Only the owner can update, and updates are validated like instantiation
+    let err = update(
+        &mut setup.app,
+        &alice,
+        ConfigUpdate {
+            soft_cap: Some(Uint128::new(1_000_000_000)),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    let err = update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            soft_cap: Some(Uint128::new(600_000_000_000)),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        ContractError::ConfigError {
+            details: "Soft cap cannot be greater than hard cap".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // Before the start, times, caps, price and limits can all change
+    update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            start_time: Some(original.start_time + 10),
+            end_time: Some(original.end_time + 100),
+            soft_cap: Some(Uint128::new(1_000_000_000)),
+            hard_cap: None,
+            eho_price: Some(Uint128::new(20_000)),
+            max_contribution_per_user: Some(Uint128::new(5_000_000_000)),
+            claim_deadline: None,
+            treasury: None,
+        },
+    )
+    .unwrap();
+    let updated = config(&setup.app);
+    assert_eq!(updated.start_time, original.start_time + 10);
+    assert_eq!(updated.end_time, original.end_time + 100);
+    assert_eq!(updated.soft_cap, Uint128::new(1_000_000_000));
+    assert_eq!(updated.hard_cap, original.hard_cap);
+    assert_eq!(updated.eho_price, Uint128::new(20_000));
+    assert_eq!(
+        updated.max_contribution_per_user,
+        Uint128::new(5_000_000_000)
+    );
+
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(111);
+    });
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    // Once started, only a single, bounded extension of the end time is allowed
+    update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            soft_cap: Some(Uint128::new(2_000_000_000)),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            end_time: Some(updated.end_time + MAX_END_TIME_EXTENSION + 1),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            end_time: Some(updated.end_time + 86_400),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let err = update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            end_time: Some(updated.end_time + 2 * 86_400),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        ContractError::ConfigError {
+            details: "The end time has already been extended".to_string()
+        },
+        err.downcast().unwrap()
+    );
+    assert_eq!(config(&setup.app).end_time, updated.end_time + 86_400);
+
+    // The treasury can still be moved
+    update(
+        &mut setup.app,
+        &admin,
+        ConfigUpdate {
+            treasury: Some(alice.to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(config(&setup.app).treasury, alice);
+
+    // Every update is logged
+    let log: ConfigChangesResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::ConfigChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(log.changes.len(), 3);
+    assert_eq!(log.changes[0].id, 0);
+    assert_eq!(log.changes[0].changed_by, admin);
+    let fields: Vec<&str> = log.changes[0]
+        .changes
+        .iter()
+        .map(|change| change.field.as_str())
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            "start_time",
+            "end_time",
+            "soft_cap",
+            "eho_price",
+            "max_contribution_per_user"
+        ]
+    );
+    assert_eq!(
+        log.changes[1].changes,
+        vec![ParamChange {
+            field: "end_time".to_string(),
+            old_value: updated.end_time.to_string(),
+            new_value: (updated.end_time + 86_400).to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_end_time_extension_with_rounds() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.rounds = vec![
+            Round {
+                name: "seed".to_string(),
+                start_time: msg.start_time,
+                end_time: msg.start_time + 30,
+                eho_price: Uint128::new(5_000),
+                round_cap: Uint128::new(6_000_000_000),
+                max_contribution_per_user: Uint128::new(5_000_000_000),
+                whitelist_required: true,
+            },
+            Round {
+                name: "public".to_string(),
+                start_time: msg.start_time + 60,
+                end_time: msg.end_time - 10,
+                eho_price: Uint128::new(10_000),
+                round_cap: msg.hard_cap,
+                max_contribution_per_user: msg.max_contribution_per_user,
+                whitelist_required: false,
+            },
+        ];
+    });
+    let (admin, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    let config = |app: &App| -> Config {
+        app.wrap()
+            .query_wasm_smart(presale_addr.clone(), &QueryMsg::Config {})
+            .unwrap()
+    };
+    let original = config(&setup.app);
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // Extending the sale pushes the final round back by the same amount
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateConfig(ConfigUpdate {
+                end_time: Some(original.end_time + 86_400),
+                ..Default::default()
+            }),
+            &[],
+        )
+        .unwrap();
+    let extended = config(&setup.app);
+    assert_eq!(extended.end_time, original.end_time + 86_400);
+    assert_eq!(extended.rounds[0].end_time, original.rounds[0].end_time);
+    assert_eq!(
+        extended.rounds[1].end_time,
+        original.rounds[1].end_time + 86_400
+    );
+
+    // Buying continues past the original end of the public round
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(3_600);
+    });
+    setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, OSMO)],
+        )
+        .unwrap();
+
+    let log: ConfigChangesResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::ConfigChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let fields: Vec<&str> = log.changes[0]
+        .changes
+        .iter()
+        .map(|change| change.field.as_str())
+        .collect();
+    assert_eq!(fields, vec!["end_time", "public.end_time"]);
+}
+
+#[test]
+fn test_merkle_whitelist() {
+    let mut setup = setup();
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    let alice_cap = Uint128::new(2_000_000_000); // 2k USDC
+
+    let (root, proofs) = merkle_tree(&[
+        merkle_leaf(&setup.alice, Some(alice_cap)),
+        merkle_leaf(&setup.bob, None),
+        merkle_leaf(&charlie, None),
+    ]);
+
+    // FAIL: Only the admin can register the root
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::UpdateMerkleRoot {
+                merkle_root: root.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::UpdateMerkleRoot {
+                merkle_root: root.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let alice_proof = WhitelistProof {
+        cap: Some(alice_cap),
+        proof: proofs[0].clone(),
+    };
+    let bob_proof = WhitelistProof {
+        cap: None,
+        proof: proofs[1].clone(),
+    };
+
+    // Proofs can be checked off the buy path
+    let is_valid: bool = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::VerifyWhitelistProof {
+                address: setup.alice.to_string(),
+                whitelist_proof: alice_proof.clone(),
+            },
+        )
+        .unwrap();
+    assert!(is_valid);
+    let is_valid: bool = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::VerifyWhitelistProof {
+                address: setup.bob.to_string(),
+                whitelist_proof: alice_proof.clone(),
+            },
+        )
+        .unwrap();
+    assert!(!is_valid);
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // FAIL: Alice claims a higher cap than the one committed to in her leaf
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: Some(WhitelistProof {
+                    cap: Some(Uint128::new(200_000_000_000)),
+                    proof: proofs[0].clone(),
+                }),
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidMerkleProof {},
+        err.downcast().unwrap()
+    );
+
+    // SUCCESS: Alice buys with a valid proof
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: Some(alice_proof.clone()),
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    let is_whitelisted: IsWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::IsWhitelisted {
+                address: setup.alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert!(is_whitelisted.whitelisted);
+    assert_eq!(is_whitelisted.cap, Some(alice_cap));
+
+    // SUCCESS: Alice is now cached and can buy again without a proof
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(500_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    // FAIL: Replaying her proof does not reset the cap committed to in her leaf
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: Some(alice_proof.clone()),
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(600_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::UserCapExceeded {}, err.downcast().unwrap());
+
+    // FAIL: Bob replays Alice's proof
+    let err = setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: Some(alice_proof),
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, OSMO)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidMerkleProof {},
+        err.downcast().unwrap()
+    );
+
+    // SUCCESS: Bob buys with his own uncapped leaf
+    setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: Some(bob_proof),
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, OSMO)],
+        )
+        .unwrap();
+
+    // FAIL: Charlie has no proof at all
+    setup
+        .app
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: charlie.to_string(),
+                amount: vec![coin(1_000_000_000, NOBLE_USDC)],
+            },
+        ))
+        .unwrap();
+    let err = setup
+        .app
+        .execute_contract(
+            charlie.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NotInWhitelist {}, err.downcast().unwrap());
+}
+
+#[test]
+fn test_multi_round_sale() {
+    // Seed round: $0.005, 6k USDC cap, 5k per user, whitelist only.
+    // Public round: $0.01, open to everyone.
+    let mut setup = setup_with(|msg| {
+        msg.rounds = vec![
+            Round {
+                name: "seed".to_string(),
+                start_time: msg.start_time,
+                end_time: msg.start_time + 30,
+                eho_price: Uint128::new(5_000),
+                round_cap: Uint128::new(6_000_000_000),
+                max_contribution_per_user: Uint128::new(5_000_000_000),
+                whitelist_required: true,
+            },
+            Round {
+                name: "public".to_string(),
+                start_time: msg.start_time + 30,
+                end_time: msg.end_time,
+                eho_price: Uint128::new(10_000),
+                round_cap: msg.hard_cap,
+                max_contribution_per_user: msg.max_contribution_per_user,
+                whitelist_required: false,
+            },
+        ];
+    });
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    setup
+        .app
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: charlie.to_string(),
+                amount: vec![coin(10_000_000_000, NOBLE_USDC)],
+            },
+        ))
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice, &charlie]),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // Enter the seed round
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // FAIL: Bob is not whitelisted for the seed round
+    let err = setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, OSMO)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NotInWhitelist {}, err.downcast().unwrap());
+
+    // SUCCESS: Alice buys 4k USDC in the seed round
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(4_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    // FAIL: Alice exceeds the seed round's per-user cap
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_500_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::UserCapExceeded {}, err.downcast().unwrap());
+
+    // FAIL: Charlie would push the seed round over its cap
+    let err = setup
+        .app
+        .execute_contract(
+            charlie.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_500_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::RoundCapReached {
+            round: "seed".to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    let current: Option<RoundResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::CurrentRound {})
+        .unwrap();
+    let current = current.unwrap();
+    assert_eq!(current.index, 0);
+    assert_eq!(current.round.name, "seed");
+    assert_eq!(current.total_raised, Uint128::new(4_000_000_000));
+
+    // Enter the public round
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(30);
+    });
+
+    // SUCCESS: Bob buys without being whitelisted, Alice buys again at the public price
+    setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, OSMO)],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    let rounds: Vec<RoundResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::Rounds {})
+        .unwrap();
+    assert_eq!(rounds.len(), 2);
+    assert_eq!(rounds[0].total_raised, Uint128::new(4_000_000_000));
+    assert_eq!(rounds[1].total_raised, Uint128::new(2_100_000_000));
+
+    // Alice: 4k USDC at $0.005 + 1k USDC at $0.01 = 800k + 100k EHO
+    let allocation: AllocationResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::EhoAllocationOf {
+                address: setup.alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(allocation.eho_allocation, Uint128::new(900_000_000_000));
+}
+
+#[test]
+fn test_invalid_rounds_rejected() {
+    let mut app = App::default();
+    let presale_code_id = app.store_code(presale_contract());
+    let admin = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3sxfqx5vwjh");
+    let start_time = app.block_info().time.seconds() + 100;
+    let round = |name: &str, start_time: u64, end_time: u64| Round {
+        name: name.to_string(),
+        start_time,
+        end_time,
+        eho_price: Uint128::new(10_000),
+        round_cap: Uint128::new(1_000_000_000),
+        max_contribution_per_user: Uint128::new(1_000_000_000),
+        whitelist_required: false,
+    };
+    let msg = InstantiateMsg {
+        admin: admin.to_string(),
+        eho_token_address: admin.to_string(),
+        accepted_rates: vec![Rate {
+            denom: NOBLE_USDC.to_string(),
+            rate: Uint128::new(1_000_000),
+            decimals: 6,
+            cap: None,
+        }],
+        accepted_cw20_rates: vec![],
+        start_time,
+        end_time: start_time + 100,
+        soft_cap: Uint128::new(1_000_000_000),
+        hard_cap: Uint128::new(10_000_000_000),
+        max_contribution_per_user: Uint128::new(1_000_000_000),
+        eho_price: Uint128::new(10_000),
+        // Overlapping rounds
+        rounds: vec![
+            round("seed", start_time, start_time + 60),
+            round("public", start_time + 50, start_time + 100),
+        ],
+        sale_mode: SaleMode::FixedPrice,
+        vesting: None,
+        oracle: None,
+        referral: None,
+        tier_bonus: None,
+        whitelist_end_time: None,
+        claim_deadline: None,
+        distribution: Distribution::Transfer,
+        treasury: None,
+    };
+    let err = app
+        .instantiate_contract(presale_code_id, admin.clone(), &msg, &[], "Presale", None)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::ConfigError { .. }
+    ));
+
+    // A vesting schedule must end at a representable time, even without a claim deadline
+    let msg = InstantiateMsg {
+        rounds: vec![],
+        vesting: Some(VestingSchedule {
+            tge_time: u64::MAX - 10,
+            tge_unlock: Decimal::percent(20),
+            cliff_seconds: 100,
+            vesting_seconds: 1_000,
+        }),
+        ..msg
+    };
+    let err = app
+        .instantiate_contract(presale_code_id, admin, &msg, &[], "Presale", None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ConfigError {
+            details: "Vesting cannot end beyond the largest timestamp".to_string()
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn test_vested_claims() {
+    // 20% unlocks at TGE (the end of the sale), the rest vests linearly over 1000s
+    // after a 100s cliff.
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000);
+        msg.vesting = Some(VestingSchedule {
+            tge_time: msg.end_time,
+            tge_unlock: Decimal::percent(20),
+            cliff_seconds: 100,
+            vesting_seconds: 1_000,
+        });
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // Bob contributes 1.1k USDC worth of OSMO for 110k EHO
+    setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, OSMO)],
+        )
+        .unwrap();
+
+    // Nothing vests while the sale is live
+    let claimable: ClaimableResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::ClaimableOf {
+                address: setup.bob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(claimable.total, Uint128::new(110_000_000_000));
+    assert_eq!(claimable.vested, Uint128::zero());
+    assert_eq!(claimable.locked, Uint128::new(110_000_000_000));
+
+    // TGE: 20% is released
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    let bob_claim = |setup: &mut TestSetup| {
+        setup.app.execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+    };
+    let bob_balance = |setup: &TestSetup| -> Uint128 {
+        let balance: cw20::BalanceResponse = setup
+            .app
+            .wrap()
+            .query_wasm_smart(
+                setup.eho_addr.clone(),
+                &cw20_eho::msg::QueryMsg::Balance {
+                    address: setup.bob.to_string(),
+                },
+            )
+            .unwrap();
+        balance.balance
+    };
+    bob_claim(&mut setup).unwrap();
+    assert_eq!(bob_balance(&setup), Uint128::new(22_000_000_000));
+
+    // FAIL: Nothing more is released during the cliff
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(50);
+    });
+    let err = bob_claim(&mut setup).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    // Halfway through linear vesting: 22k + 44k vested, 22k already claimed
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(549);
+    });
+    let claimable: ClaimableResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::ClaimableOf {
+                address: setup.bob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        claimable,
+        ClaimableResponse {
+            total: Uint128::new(110_000_000_000),
+            vested: Uint128::new(66_000_000_000),
+            claimed: Uint128::new(22_000_000_000),
+            locked: Uint128::new(44_000_000_000),
+            claimable: Uint128::new(44_000_000_000),
+        }
+    );
+    bob_claim(&mut setup).unwrap();
+    assert_eq!(bob_balance(&setup), Uint128::new(66_000_000_000));
+
+    // Fully vested
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(1_000);
+    });
+    bob_claim(&mut setup).unwrap();
+    assert_eq!(bob_balance(&setup), Uint128::new(110_000_000_000));
+    let err = bob_claim(&mut setup).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+}
+
+fn cw20_balance(app: &App, token: &Addr, address: &Addr) -> Uint128 {
+    let balance: cw20::BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token.clone(),
+            &cw20_eho::msg::QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    balance.balance
+}
+
+/// Buys from the presale by sending `amount` of `token` with a `ReceiveMsg::Buy` hook.
+fn buy_with_cw20(
+    setup: &mut TestSetup,
+    buyer: &Addr,
+    token: &Addr,
+    amount: u128,
+) -> AnyResult<AppResponse> {
+    setup.app.execute_contract(
+        buyer.clone(),
+        token.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: setup.presale_addr.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&ReceiveMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+}
+
+#[test]
+fn test_cw20_payment_and_refund() {
+    let mut setup = setup();
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // FAIL: The EHO token itself is not an accepted payment token
+    let (alice, eho_addr, cw20_usdc_addr) = (
+        setup.alice.clone(),
+        setup.eho_addr.clone(),
+        setup.cw20_usdc_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            eho_addr.clone(),
+            &Cw20ExecuteMsg::Mint {
+                recipient: alice.to_string(),
+                amount: Uint128::new(1_000_000),
+            },
+            &[],
+        )
+        .unwrap();
+    let err = buy_with_cw20(&mut setup, &alice, &eho_addr, 1_000_000).unwrap_err();
+    assert_eq!(
+        ContractError::UnacceptedPaymentDenom {
+            denom: eho_addr.to_string()
+        },
+        err.downcast().unwrap()
+    );
+
+    // SUCCESS: Alice pays 3k in CW20 USDC and 2k in native USDC
+    buy_with_cw20(&mut setup, &alice, &cw20_usdc_addr, 3_000_000_000).unwrap();
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    let contributions: Vec<Contribution> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::ContributionsOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        contributions,
+        vec![
+            Contribution {
+                info: AssetInfo::Cw20 {
+                    contract_addr: cw20_usdc_addr.clone()
+                },
+                amount: Uint128::new(3_000_000_000),
+                usdc_value: Uint128::new(3_000_000_000),
+            },
+            Contribution {
+                info: AssetInfo::Native {
+                    denom: NOBLE_USDC.to_string()
+                },
+                amount: Uint128::new(2_000_000_000),
+                usdc_value: Uint128::new(2_000_000_000),
+            },
+        ]
+    );
+    let total: Uint128 = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::TotalContributionOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(total, Uint128::new(5_000_000_000));
+
+    // The sale ends below the soft cap and Alice gets both assets back
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    let usdc_before = setup
+        .app
+        .wrap()
+        .query_balance(alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::RequestRefund {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &cw20_usdc_addr, &alice),
+        Uint128::new(50_000_000_000)
+    );
+    assert_eq!(
+        setup
+            .app
+            .wrap()
+            .query_balance(alice.clone(), NOBLE_USDC)
+            .unwrap()
+            .amount,
+        usdc_before + Uint128::new(2_000_000_000)
+    );
+}
+
+#[test]
+fn test_cw20_payment_withdrawal() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let (alice, cw20_usdc_addr) = (setup.alice.clone(), setup.cw20_usdc_addr.clone());
+    buy_with_cw20(&mut setup, &alice, &cw20_usdc_addr, 3_000_000_000).unwrap();
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+
+    // Alice receives 3k USDC / $0.01 = 300k EHO
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &alice),
+        Uint128::new(300_000_000_000)
+    );
+
+    // The admin withdraws the CW20 USDC raised
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::WithdrawFunds {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &cw20_usdc_addr, &setup.admin),
+        Uint128::new(3_000_000_000)
+    );
+    assert_eq!(
+        cw20_balance(&setup.app, &cw20_usdc_addr, &setup.presale_addr),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_rate_updates_keep_locked_valuation() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    let (admin, alice, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.presale_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // Alice buys with 100 ATOM at $7
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(100_000_000, ATOM)],
+        )
+        .unwrap();
+
+    // Only the admin can update rates
+    let err = setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateRates {
+                rates: vec![],
+                cw20_rates: vec![],
+                remove: vec![ATOM.to_string()],
+                remove_caps: vec![],
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    // ATOM doubles in price; Alice's existing purchase keeps its $700 valuation
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateRates {
+                rates: vec![Rate {
+                    denom: ATOM.to_string(),
+                    rate: Uint128::new(14_000_000),
+                    decimals: 6,
+                    cap: None,
+                }],
+                cw20_rates: vec![],
+                remove: vec![OSMO.to_string()],
+                remove_caps: vec![],
+            },
+            &[],
+        )
+        .unwrap();
+    let total: Uint128 = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::TotalContributionOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(total, Uint128::new(700_000_000));
+
+    // New purchases use the updated rate
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(100_000_000, ATOM)],
+        )
+        .unwrap();
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.total_usdc_raised, Uint128::new(2_100_000_000));
+    let allocation: AllocationResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::EhoAllocationOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(allocation.eho_allocation, Uint128::new(210_000_000_000));
+
+    // The removed denom is no longer accepted
+    let rates: Vec<AcceptedRateResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::AcceptedRates {})
+        .unwrap();
+    assert!(rates.iter().all(|rate| rate.denom != OSMO));
+    setup
+        .app
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: alice.to_string(),
+                amount: vec![coin(1_000_000, OSMO)],
+            },
+        ))
+        .unwrap();
+    let err = setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000, OSMO)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UnacceptedPaymentDenom {
+            denom: OSMO.to_string()
+        }
+    );
+
+    // Removing ATOM after the sale still lets the admin withdraw the ATOM raised
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateRates {
+                rates: vec![],
+                cw20_rates: vec![],
+                remove: vec![ATOM.to_string()],
+                remove_caps: vec![],
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::WithdrawFunds {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        setup
+            .app
+            .wrap()
+            .query_balance(admin.clone(), ATOM)
+            .unwrap()
+            .amount,
+        Uint128::new(200_000_000)
+    );
+}
+
+#[test]
+fn test_oracle_priced_purchases() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    let (admin, alice, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.presale_addr.clone(),
+    );
+    let oracle_code_id = setup.app.store_code(mock_oracle_contract());
+    let oracle_addr = setup
+        .app
+        .instantiate_contract(
+            oracle_code_id,
+            admin.clone(),
+            &Empty {},
+            &[],
+            "Oracle",
+            None,
+        )
+        .unwrap();
+    let set_atom_price = |app: &mut App, price: u128, updated_at: u64| {
+        app.execute_contract(
+            admin.clone(),
+            oracle_addr.clone(),
+            &MockOracleExecuteMsg::SetPrice {
+                denom: ATOM.to_string(),
+                price: Uint128::new(price),
+                updated_at,
+            },
+            &[],
+        )
+        .unwrap();
+    };
+    let buy_atom = |app: &mut App, amount: u128| {
+        app.execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(amount, ATOM)],
+        )
+    };
+    let update_oracle = |app: &mut App, fallback_to_static: bool| {
+        app.execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateOracle {
+                oracle: Some(OracleConfig {
+                    contract: oracle_addr.clone(),
+                    max_staleness: 60,
+                    max_deviation: Some(Decimal::percent(20)),
+                    fallback_to_static,
+                }),
+            },
+            &[],
+        )
+        .unwrap();
+    };
+
+    update_oracle(&mut setup.app, false);
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let now = setup.app.block_info().time.seconds();
+
+    // A fresh oracle price within the band values 100 ATOM at $8 instead of the static $7
+    set_atom_price(&mut setup.app, 8_000_000, now);
+    buy_atom(&mut setup.app, 100_000_000).unwrap();
+    let total: Uint128 = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::TotalContributionOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(total, Uint128::new(800_000_000));
+
+    // A price more than 20% away from the static rate is rejected
+    set_atom_price(&mut setup.app, 9_000_000, now);
+    let err = buy_atom(&mut setup.app, 100_000_000).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::OraclePriceDeviation {
+            denom: ATOM.to_string(),
+            price: Uint128::new(9_000_000),
+            reference: Uint128::new(7_000_000),
+        }
+    );
+
+    // A stale price is rejected without a fallback
+    set_atom_price(&mut setup.app, 8_000_000, now);
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(61);
+    });
+    let err = buy_atom(&mut setup.app, 100_000_000).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::OraclePriceStale {
+            denom: ATOM.to_string()
+        }
+    );
+
+    // With the fallback enabled, the stale price is replaced by the static rate
+    update_oracle(&mut setup.app, true);
+    buy_atom(&mut setup.app, 100_000_000).unwrap();
+    let total: Uint128 = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::TotalContributionOf {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(total, Uint128::new(1_500_000_000));
+}
+
+#[test]
+fn test_payment_decimals_normalisation() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.accepted_rates.push(Rate {
+            denom: WETH.to_string(),
+            rate: Uint128::new(2_000_000_000), // $2,000 per WETH
+            decimals: 18,
+            cap: None,
+        });
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    setup
+        .app
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: bob.to_string(),
+                amount: vec![coin(1_000_000_000_000_000_000, WETH)],
+            },
+        ))
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    let rates: Vec<AcceptedRateResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::AcceptedRates {})
+        .unwrap();
+    assert_eq!(
+        rates
+            .iter()
+            .find(|rate| rate.denom == WETH)
+            .unwrap()
+            .decimals,
+        18
+    );
+
+    // Dust worth less than one USDC base unit is rejected
+    let err = setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(100_000, WETH)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidZeroAmount {}, err.downcast().unwrap());
+
+    // $2,000 paid as 2,000 USDC (6 decimals) and as 1 WETH (18 decimals)
+    let mut usdc_values = vec![];
+    for (buyer, funds) in [
+        (&alice, coin(2_000_000_000, NOBLE_USDC)),
+        (&bob, coin(1_000_000_000_000_000_000, WETH)),
+    ] {
+        let res = setup
+            .app
+            .execute_contract(
+                buyer.clone(),
+                presale_addr.clone(),
+                &ExecuteMsg::Buy {
+                    whitelist_proof: None,
+                    referrer: None,
+                    kyc_voucher: None,
+                },
+                &[funds],
+            )
+            .unwrap();
+        let wasm = res.events.iter().find(|e| e.ty == "wasm").unwrap();
+        let usdc_value = wasm
+            .attributes
+            .iter()
+            .find(|a| a.key == "usdc_value_added")
+            .unwrap()
+            .value
+            .clone();
+        usdc_values.push(usdc_value);
+    }
+    assert_eq!(usdc_values, vec!["2000000000", "2000000000"]);
+
+    // Both buyers get 2,000 USDC / $0.01 = 200k EHO
+    for buyer in [&alice, &bob] {
+        let allocation: AllocationResponse = setup
+            .app
+            .wrap()
+            .query_wasm_smart(
+                presale_addr.clone(),
+                &QueryMsg::EhoAllocationOf {
+                    address: buyer.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(allocation.eho_allocation, Uint128::new(200_000_000_000));
+    }
+}
+
+#[test]
+fn test_per_denom_caps() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        // At most $800 may be raised in OSMO
+        let osmo = msg
+            .accepted_rates
+            .iter_mut()
+            .find(|rate| rate.denom == OSMO)
+            .unwrap();
+        osmo.cap = Some(Uint128::new(800_000_000));
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+    };
+
+    // 1,000 OSMO at $0.55 is $550, within the cap
+    buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap();
+
+    // Another $550 would take OSMO past its $800 cap
+    let err = buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        ContractError::DenomCapExceeded {
+            denom: OSMO.to_string(),
+            remaining: Uint128::new(250_000_000),
+        },
+        err.downcast().unwrap()
+    );
+
+    // Other assets are unaffected
+    buy(&mut setup.app, &alice, coin(1_000_000_000, NOBLE_USDC)).unwrap();
+
+    let rates: Vec<AcceptedRateResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::AcceptedRates {})
+        .unwrap();
+    let osmo = rates.iter().find(|rate| rate.denom == OSMO).unwrap();
+    assert_eq!(osmo.cap, Some(Uint128::new(800_000_000)));
+    assert_eq!(osmo.remaining, Some(Uint128::new(250_000_000)));
+    let usdc = rates.iter().find(|rate| rate.denom == NOBLE_USDC).unwrap();
+    assert_eq!(usdc.cap, None);
+    assert_eq!(usdc.remaining, None);
+
+    // A zero cap is rejected
+    let update_osmo = |cap: Option<Uint128>, remove_caps: Vec<String>| ExecuteMsg::UpdateRates {
+        rates: vec![Rate {
+            denom: OSMO.to_string(),
+            rate: Uint128::new(500_000),
+            decimals: 6,
+            cap,
+        }],
+        cw20_rates: vec![],
+        remove: vec![],
+        remove_caps,
+    };
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &update_osmo(Some(Uint128::zero()), vec![]),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidZeroAmount {}, err.downcast().unwrap());
+
+    // Refreshing the rate without a cap keeps the existing one
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &update_osmo(None, vec![]),
+            &[],
+        )
+        .unwrap();
+    let rates: Vec<AcceptedRateResponse> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::AcceptedRates {})
+        .unwrap();
+    let osmo = rates.iter().find(|rate| rate.denom == OSMO).unwrap();
+    assert_eq!(osmo.rate, Uint128::new(500_000));
+    assert_eq!(osmo.cap, Some(Uint128::new(800_000_000)));
+    assert_eq!(osmo.remaining, Some(Uint128::new(250_000_000)));
+    let err = buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        ContractError::DenomCapExceeded {
+            denom: OSMO.to_string(),
+            remaining: Uint128::new(250_000_000),
+        },
+        err.downcast().unwrap()
+    );
+
+    // The cap is only lifted explicitly
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &update_osmo(None, vec![OSMO.to_string()]),
+            &[],
+        )
+        .unwrap();
+    buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap();
+}
+
+#[test]
+fn test_referral_bonuses() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.referral = Some(ReferralConfig {
+            buyer_bonus: Decimal::percent(5),
+            referrer_bonus: Decimal::percent(10),
+        });
+    });
+    let charlie = Addr::unchecked("cosmwasm1qypqxpq9qcrsszgszyfpx9q4zct3s9pp76qy8a");
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, referrer: &Addr| {
+        app.execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: Some(referrer.to_string()),
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+    };
+
+    // Buyers cannot refer themselves
+    let err = buy(&mut setup.app, &alice).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidReferrer {}
+    );
+
+    // Alice buys 100k EHO twice through Bob and once through Charlie
+    buy(&mut setup.app, &bob).unwrap();
+    buy(&mut setup.app, &bob).unwrap();
+    buy(&mut setup.app, &charlie).unwrap();
+
+    let allocation_of = |app: &App, user: &Addr| -> AllocationResponse {
+        app.wrap()
+            .query_wasm_smart(
+                presale_addr.clone(),
+                &QueryMsg::EhoAllocationOf {
+                    address: user.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    // Alice gets 300k EHO plus a 5% buyer bonus
+    let alice_allocation = allocation_of(&setup.app, &alice);
+    assert_eq!(
+        alice_allocation.eho_allocation,
+        Uint128::new(315_000_000_000)
+    );
+    assert_eq!(
+        alice_allocation.referral_bonus,
+        Uint128::new(15_000_000_000)
+    );
+    // Bob earns 10% of the 200k EHO he referred without buying himself
+    let bob_allocation = allocation_of(&setup.app, &bob);
+    assert_eq!(bob_allocation.eho_allocation, Uint128::new(20_000_000_000));
+    assert_eq!(bob_allocation.referral_bonus, Uint128::new(20_000_000_000));
+
+    // The leaderboard pages through referrers by address
+    let page: ReferralStatsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::ReferralStats {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(page.referrers.len(), 1);
+    let next: ReferralStatsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::ReferralStats {
+                start_after: Some(page.referrers[0].referrer.clone()),
+                limit: None,
+            },
+        )
+        .unwrap();
+    let mut referrers = page.referrers;
+    referrers.extend(next.referrers);
+    let bob_stats = referrers
+        .iter()
+        .find(|info| info.referrer == bob.as_str())
+        .unwrap();
+    assert_eq!(
+        bob_stats.stats,
+        ReferrerStats {
+            referred_volume: Uint128::new(2_000_000_000),
+            referred_purchases: 2,
+            bonus_eho: Uint128::new(20_000_000_000),
+        }
+    );
+    let charlie_stats = referrers
+        .iter()
+        .find(|info| info.referrer == charlie.as_str())
+        .unwrap();
+    assert_eq!(
+        charlie_stats.stats.referred_volume,
+        Uint128::new(1_000_000_000)
+    );
+    assert_eq!(referrers.len(), 2);
+
+    // Both claim their bonuses once the sale succeeds
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    for (user, expected) in [(&alice, 315_000_000_000), (&bob, 20_000_000_000)] {
+        setup
+            .app
+            .execute_contract(
+                user.clone(),
+                presale_addr.clone(),
+                &ExecuteMsg::ClaimTokens {},
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            cw20_balance(&setup.app, &setup.eho_addr, user),
+            Uint128::new(expected)
+        );
+    }
+}
+
+#[test]
+fn test_tier_bonuses() {
+    let tiers = vec![
+        BonusTier {
+            threshold: Uint128::new(1_000_000_000), // $1k
+            bonus: Decimal::percent(5),
+        },
+        BonusTier {
+            threshold: Uint128::new(10_000_000_000), // $10k
+            bonus: Decimal::percent(10),
+        },
+    ];
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.tier_bonus = Some(TierBonusConfig {
+            tiers: tiers.clone(),
+            pool: Uint128::new(10_000_000_000), // 10k EHO
+        });
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+
+    // Tiers must increase monotonically
+    let presale_code_id = setup.app.store_code(presale_contract());
+    let start_time = setup.app.block_info().time.seconds() + 100;
+    let err = setup
+        .app
+        .instantiate_contract(
+            presale_code_id,
+            admin.clone(),
+            &InstantiateMsg {
+                admin: admin.to_string(),
+                eho_token_address: setup.eho_addr.to_string(),
+                accepted_rates: vec![Rate {
+                    denom: NOBLE_USDC.to_string(),
+                    rate: Uint128::new(1_000_000),
+                    decimals: 6,
+                    cap: None,
+                }],
+                accepted_cw20_rates: vec![],
+                start_time,
+                end_time: start_time + 100,
+                soft_cap: Uint128::new(1_000_000_000),
+                hard_cap: Uint128::new(10_000_000_000),
+                max_contribution_per_user: Uint128::new(1_000_000_000),
+                eho_price: Uint128::new(10_000),
+                rounds: vec![],
+                sale_mode: SaleMode::FixedPrice,
+                vesting: None,
+                oracle: None,
+                referral: None,
+                tier_bonus: Some(TierBonusConfig {
+                    tiers: tiers.into_iter().rev().collect(),
+                    pool: Uint128::new(10_000_000_000),
+                }),
+                whitelist_end_time: None,
+                claim_deadline: None,
+                distribution: Distribution::Transfer,
+                treasury: None,
+            },
+            &[],
+            "Presale",
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::ConfigError { .. }
+    ));
+
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+        .unwrap();
+    };
+    let allocation_of = |app: &App, user: &Addr| -> AllocationResponse {
+        app.wrap()
+            .query_wasm_smart(
+                presale_addr.clone(),
+                &QueryMsg::EhoAllocationOf {
+                    address: user.to_string(),
+                },
+            )
+            .unwrap()
+    };
+
+    // $500 is below the first tier
+    buy(&mut setup.app, &alice, coin(500_000_000, NOBLE_USDC));
+    assert_eq!(
+        allocation_of(&setup.app, &alice).tier_bonus,
+        Uint128::zero()
+    );
+
+    // $1,100 in total earns 5% on all 110k EHO bought
+    buy(&mut setup.app, &alice, coin(600_000_000, NOBLE_USDC));
+    let allocation = allocation_of(&setup.app, &alice);
+    assert_eq!(allocation.tier_bonus, Uint128::new(5_500_000_000));
+    assert_eq!(allocation.eho_allocation, Uint128::new(115_500_000_000));
+
+    // Reaching $10,100 would earn 10% of 1.01M EHO, but only 4.5k EHO is left in the pool
+    buy(&mut setup.app, &alice, coin(9_000_000_000, NOBLE_USDC));
+    let allocation = allocation_of(&setup.app, &alice);
+    assert_eq!(allocation.tier_bonus, Uint128::new(10_000_000_000));
+    assert_eq!(allocation.eho_allocation, Uint128::new(1_020_000_000_000));
+
+    // Bob reaches the first tier once the pool is exhausted and gets no bonus
+    buy(&mut setup.app, &bob, coin(2_000_000_000, OSMO));
+    let allocation = allocation_of(&setup.app, &bob);
+    assert_eq!(allocation.tier_bonus, Uint128::zero());
+    assert_eq!(allocation.eho_allocation, Uint128::new(110_000_000_000));
+
+    // The bonus is paid out with the claim
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &alice),
+        Uint128::new(1_020_000_000_000)
+    );
+
+    // In a Dutch auction the bonus is earned on the EHO bought at the bid price, not at the
+    // lower price the auction clears at
+    let mut auction = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.sale_mode = SaleMode::DutchAuction {
+            start_price: Uint128::new(50_000), // $0.05
+            floor_price: Uint128::new(10_000), // $0.01
+            step_seconds: 10,
+            supply: Uint128::new(1_000_000_000_000), // 1M EHO
+        };
+        msg.tier_bonus = Some(TierBonusConfig {
+            tiers: vec![BonusTier {
+                threshold: Uint128::new(1_000_000_000), // $1k
+                bonus: Decimal::percent(5),
+            }],
+            pool: Uint128::new(10_000_000_000), // 10k EHO
+        });
+    });
+    let presale_addr = auction.presale_addr.clone();
+    auction
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+        .unwrap();
+    };
+    let allocation_of = |app: &App, user: &Addr| -> AllocationResponse {
+        app.wrap()
+            .query_wasm_smart(
+                presale_addr.clone(),
+                &QueryMsg::EhoAllocationOf {
+                    address: user.to_string(),
+                },
+            )
+            .unwrap()
+    };
+
+    // Bob's $1,100 at $0.046 buys 23,913 EHO and earns 5% of it
+    auction.app.update_block(|block| {
+        block.time = block.time.plus_seconds(115);
+    });
+    buy(&mut auction.app, &bob, coin(2_000_000_000, OSMO));
+    assert_eq!(
+        allocation_of(&auction.app, &bob).tier_bonus,
+        Uint128::new(1_195_652_173)
+    );
+
+    // Alice sells out the supply at $0.03, which leaves Bob's bonus unchanged
+    auction.app.update_block(|block| {
+        block.time = block.time.plus_seconds(40);
+    });
+    buy(&mut auction.app, &alice, coin(30_000_000_000, NOBLE_USDC));
+    let allocation = allocation_of(&auction.app, &bob);
+    assert_eq!(allocation.tier_bonus, Uint128::new(1_195_652_173));
+    assert_eq!(allocation.eho_allocation, Uint128::new(25_108_695_651));
+    auction
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&auction.app, &auction.eho_addr, &bob),
+        Uint128::new(25_108_695_651)
+    );
+}
+
+fn dutch_auction_setup(step_seconds: u64) -> TestSetup {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.sale_mode = SaleMode::DutchAuction {
+            start_price: Uint128::new(50_000), // $0.05
+            floor_price: Uint128::new(10_000), // $0.01
+            step_seconds,
+            supply: Uint128::new(1_000_000_000_000), // 1M EHO
+        };
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice, &setup.bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup
+}
+
+fn current_price(setup: &TestSetup) -> Uint128 {
+    setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::CurrentPrice {})
+        .unwrap()
+}
+
+#[test]
+fn test_dutch_auction_settles_at_floor() {
+    let mut setup = dutch_auction_setup(0);
+    assert_eq!(current_price(&setup), Uint128::new(50_000));
+
+    // Halfway through the window the price has declined linearly to $0.03
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(150);
+    });
+    assert_eq!(current_price(&setup), Uint128::new(30_000));
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(3_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+
+    // $3k does not sell out the supply, so the auction clears at the floor price
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(50);
+    });
+    assert_eq!(current_price(&setup), Uint128::new(10_000));
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+
+    // Alice keeps the 100k EHO her bid bought at $0.03 and is refunded the $2k paid above $0.01
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &setup.alice),
+        Uint128::new(100_000_000_000)
+    );
+    let usdc = setup
+        .app
+        .wrap()
+        .query_balance(setup.alice.clone(), NOBLE_USDC)
+        .unwrap();
+    assert_eq!(usdc.amount, Uint128::new(299_000_000_000));
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.clearing_price, Some(Uint128::new(10_000)));
+}
+
+#[test]
+fn test_dutch_auction_sells_out_at_clearing_price() {
+    // The price drops by $0.004 every 10 seconds
+    let mut setup = dutch_auction_setup(10);
+    let (alice, bob, presale_addr) = (
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+
+    // Bob bids $1,100 in OSMO early, at $0.046
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(115);
+    });
+    assert_eq!(current_price(&setup), Uint128::new(46_000));
+    setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, OSMO)],
+        )
+        .unwrap();
+
+    // At $0.03 the supply is worth $30k: Alice's $30k bid fills the remaining $28.9k,
+    // the rest is refunded and the auction closes
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(40);
+    });
+    assert_eq!(current_price(&setup), Uint128::new(30_000));
+    let usdc_before = setup
+        .app
+        .wrap()
+        .query_balance(alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(30_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    let usdc_after = setup
+        .app
+        .wrap()
+        .query_balance(alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    assert_eq!(usdc_before - usdc_after, Uint128::new(28_900_000_000));
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.sale_status, SaleStatus::Succeeded);
+    assert_eq!(state.clearing_price, Some(Uint128::new(30_000)));
+    assert_eq!(current_price(&setup), Uint128::new(30_000));
+
+    // Each bid keeps the EHO it bought at its own price
+    for (user, expected) in [(&bob, 23_913_043_478), (&alice, 963_333_333_333)] {
+        let allocation: AllocationResponse = setup
+            .app
+            .wrap()
+            .query_wasm_smart(
+                presale_addr.clone(),
+                &QueryMsg::EhoAllocationOf {
+                    address: user.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(allocation.eho_allocation, Uint128::new(expected));
+    }
+
+    // Bob paid $1,100 for 23,913 EHO worth $717.39 at the clearing price, so the $382.61
+    // above it is owed back in OSMO and stays in the contract when the funds are withdrawn
+    let allocation: AllocationResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::EhoAllocationOf {
+                address: bob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        allocation.refund,
+        vec![Asset {
+            info: AssetInfo::Native {
+                denom: OSMO.to_string()
+            },
+            amount: Uint128::new(695_652_173),
+        }]
+    );
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::WithdrawFunds {},
+            &[],
+        )
+        .unwrap();
+    let osmo = setup
+        .app
+        .wrap()
+        .query_balance(setup.admin.clone(), OSMO)
+        .unwrap();
+    assert_eq!(osmo.amount, Uint128::new(1_304_347_827));
+    setup
+        .app
+        .execute_contract(
+            bob.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &bob),
+        Uint128::new(23_913_043_478)
+    );
+    let osmo = setup.app.wrap().query_balance(bob.clone(), OSMO).unwrap();
+    assert_eq!(osmo.amount, Uint128::new(695_652_173));
+}
+
+#[test]
+fn test_enumeration_and_sale_stats() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.hard_cap = Uint128::new(10_000_000_000);
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice, &bob]),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // The whitelist pages by address
+    let first: AllWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::AllWhitelisted {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(first.addresses.len(), 1);
+    let rest: AllWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::AllWhitelisted {
+                start_after: Some(first.addresses[0].clone()),
+                limit: None,
+            },
+        )
+        .unwrap();
+    let mut whitelisted = [first.addresses, rest.addresses].concat();
+    whitelisted.sort();
+    let mut expected = vec![alice.to_string(), bob.to_string()];
+    expected.sort();
+    assert_eq!(whitelisted, expected);
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    for (buyer, funds) in [
+        (&alice, coin(1_000_000_000, NOBLE_USDC)),
+        (&alice, coin(100_000_000, ATOM)),
+        (&bob, coin(2_000_000_000, OSMO)),
+    ] {
+        setup
+            .app
+            .execute_contract(
+                buyer.clone(),
+                presale_addr.clone(),
+                &ExecuteMsg::Buy {
+                    whitelist_proof: None,
+                    referrer: None,
+                    kyc_voucher: None,
+                },
+                &[funds],
+            )
+            .unwrap();
+    }
+
+    let contributors: AllContributionsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::AllContributions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(contributors.contributors.len(), 2);
+    let alice_info = contributors
+        .contributors
+        .iter()
+        .find(|info| info.address == alice.as_str())
+        .unwrap();
+    assert_eq!(alice_info.contributions.len(), 2);
+
+    // Alice contributed $1,700 and Bob $1,100
+    let stats: SaleStatsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::SaleStats {})
+        .unwrap();
+    assert_eq!(stats.contributor_count, 2);
+    assert_eq!(stats.total_usdc_raised, Uint128::new(2_800_000_000));
+    assert_eq!(stats.remaining_capacity, Uint128::new(7_200_000_000));
+    assert_eq!(stats.raised_by_asset.len(), 3);
+    assert!(stats.raised_by_asset.contains(&Asset {
+        info: AssetInfo::Native {
+            denom: OSMO.to_string()
+        },
+        amount: Uint128::new(2_000_000_000),
+    }));
+
+    // Claims are counted once per contributor
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    let stats: SaleStatsResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::SaleStats {})
+        .unwrap();
+    assert_eq!(stats.claimed_count, 1);
+    assert_eq!(stats.refunded_count, 0);
+
+    // Refunds of a failed sale are counted once per contributor
+    let mut failed = setup_with(|_| {});
+    two_contributor_sale(&mut failed);
+    for user in [failed.alice.clone(), failed.bob.clone()] {
+        failed
+            .app
+            .execute_contract(
+                user,
+                failed.presale_addr.clone(),
+                &ExecuteMsg::RequestRefund {},
+                &[],
+            )
+            .unwrap();
+    }
+    let stats: SaleStatsResponse = failed
+        .app
+        .wrap()
+        .query_wasm_smart(failed.presale_addr.clone(), &QueryMsg::SaleStats {})
+        .unwrap();
+    assert_eq!(stats.refunded_count, 2);
+    assert_eq!(stats.claimed_count, 0);
+}
+
+#[test]
+fn test_per_address_whitelist_limits() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.max_contribution_per_user = Uint128::new(1_000_000_000); // $1k
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+
+    // A minimum above the cap is rejected
+    let err = setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: vec![WhitelistAddition {
+                    address: alice.to_string(),
+                    cap: Some(Uint128::new(1_000_000_000)),
+                    min: Some(Uint128::new(2_000_000_000)),
+                }],
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::ConfigError { .. }
+    ));
+
+    // Alice is a strategic investor with a $5k allocation and a $2k minimum;
+    // Bob uses the sale-wide $1k cap
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: vec![
+                    WhitelistAddition {
+                        address: alice.to_string(),
+                        cap: Some(Uint128::new(5_000_000_000)),
+                        min: Some(Uint128::new(2_000_000_000)),
+                    },
+                    WhitelistAddition {
+                        address: bob.to_string(),
+                        cap: None,
+                        min: None,
+                    },
+                ],
+            },
+            &[],
+        )
+        .unwrap();
+    let entry: IsWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::IsWhitelisted {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        entry,
+        IsWhitelistedResponse {
+            whitelisted: true,
+            cap: Some(Uint128::new(5_000_000_000)),
+            min: Some(Uint128::new(2_000_000_000)),
+        }
+    );
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+    };
+
+    // Alice's first purchase must reach her minimum
+    let err = buy(&mut setup.app, &alice, coin(1_000_000_000, NOBLE_USDC)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::BelowMinimumContribution {
+            min: Uint128::new(2_000_000_000)
+        }
+    );
+    buy(&mut setup.app, &alice, coin(2_000_000_000, NOBLE_USDC)).unwrap();
+    buy(&mut setup.app, &alice, coin(3_000_000_000, NOBLE_USDC)).unwrap();
+    let err = buy(&mut setup.app, &alice, coin(1_000_000, NOBLE_USDC)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UserCapExceeded {}
+    );
+
+    // Bob is held to the sale-wide cap: 2,000 OSMO is worth $1,100
+    let err = buy(&mut setup.app, &bob, coin(2_000_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UserCapExceeded {}
+    );
+    buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap();
+}
+
+#[test]
+fn test_whitelist_window_then_public_sale() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.whitelist_end_time = Some(msg.start_time + 50);
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    let config: Config = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.whitelist_end_time, Some(config.start_time + 50));
+    let access_mode = |app: &App| -> AccessMode {
+        let state: State = app
+            .wrap()
+            .query_wasm_smart(presale_addr.clone(), &QueryMsg::State {})
+            .unwrap();
+        state.access_mode
+    };
+    let buy = |app: &mut App, buyer: &Addr, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[funds],
+        )
+    };
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(access_mode(&setup.app), AccessMode::WhitelistOnly);
+
+    // During the whitelist window only Alice can buy
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    assert_eq!(access_mode(&setup.app), AccessMode::WhitelistOnly);
+    buy(&mut setup.app, &alice, coin(1_000_000_000, NOBLE_USDC)).unwrap();
+    let err = buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotInWhitelist {}
+    );
+
+    // Once the window ends the sale is public
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(50);
+    });
+    assert_eq!(access_mode(&setup.app), AccessMode::Public);
+    buy(&mut setup.app, &bob, coin(1_000_000_000, OSMO)).unwrap();
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.total_usdc_raised, Uint128::new(1_550_000_000));
+}
+
+// --- KYC Voucher Helpers ---
+fn kyc_signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32].into()).unwrap()
+}
+
+fn sign_voucher(
+    key: &SigningKey,
+    presale: &Addr,
+    buyer: &Addr,
+    cap: Option<Uint128>,
+    expiry: u64,
+    nonce: u64,
+) -> KycVoucher {
+    let cap_str = cap.map(|cap| cap.to_string()).unwrap_or_default();
+    let preimage = format!("{}:{}:{}:{}:{}", presale, buyer, cap_str, expiry, nonce);
+    let hash = Sha256::digest(preimage.as_bytes());
+    let signature: Signature = key.sign_prehash(&hash).unwrap();
+    KycVoucher {
+        cap,
+        expiry,
+        nonce,
+        signature: Binary::from(signature.to_bytes().as_slice()),
+    }
+}
+
+#[test]
+fn test_kyc_voucher_purchases() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+    });
+    let (admin, alice, bob, presale_addr) = (
+        setup.admin.clone(),
+        setup.alice.clone(),
+        setup.bob.clone(),
+        setup.presale_addr.clone(),
+    );
+    let key = kyc_signing_key();
+    let expiry = setup.app.block_info().time.seconds() + 1_000;
+    let buy = |app: &mut App, buyer: &Addr, voucher: Option<KycVoucher>, funds: Coin| {
+        app.execute_contract(
+            buyer.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: voucher,
+            },
+            &[funds],
+        )
+    };
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // Vouchers are refused until a signer is registered
+    let voucher = sign_voucher(&key, &presale_addr, &alice, None, expiry, 1);
+    let err = buy(
+        &mut setup.app,
+        &alice,
+        Some(voucher),
+        coin(1_000_000_000, NOBLE_USDC),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::KycSignerNotSet {}
+    );
+
+    // Only the admin registers the signer, which must be a secp256k1 key
+    let pubkey = Binary::from(key.verifying_key().to_sec1_bytes().as_ref());
+    setup
+        .app
+        .execute_contract(
+            alice.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateKycSigner {
+                pubkey: Some(pubkey.clone()),
+            },
+            &[],
+        )
+        .unwrap_err();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateKycSigner {
+                pubkey: Some(Binary::from(vec![2u8; 20])),
+            },
+            &[],
+        )
+        .unwrap_err();
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::UpdateKycSigner {
+                pubkey: Some(pubkey.clone()),
+            },
+            &[],
+        )
+        .unwrap();
+    let signer: Option<Binary> = setup
+        .app
+        .wrap()
+        .query_wasm_smart(presale_addr.clone(), &QueryMsg::KycSigner {})
+        .unwrap();
+    assert_eq!(signer, Some(pubkey));
+
+    // A capped voucher admits Alice, who is not on the whitelist
+    let cap = Some(Uint128::new(5_000_000_000));
+    let voucher = sign_voucher(&key, &presale_addr, &alice, cap, expiry, 1);
+    buy(
+        &mut setup.app,
+        &alice,
+        Some(voucher.clone()),
+        coin(1_000_000_000, NOBLE_USDC),
+    )
+    .unwrap();
+    let whitelisted: IsWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::IsWhitelisted {
+                address: alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert!(whitelisted.whitelisted);
+    assert_eq!(whitelisted.cap, cap);
+
+    // Later purchases need no voucher, and the voucher's cap applies
+    buy(
+        &mut setup.app,
+        &alice,
+        None,
+        coin(4_000_000_000, NOBLE_USDC),
+    )
+    .unwrap();
+    let err = buy(&mut setup.app, &alice, None, coin(1_000_000, NOBLE_USDC)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UserCapExceeded {}
+    );
+
+    // A nonce cannot be redeemed twice
+    let err = buy(
+        &mut setup.app,
+        &alice,
+        Some(voucher),
+        coin(1_000_000, NOBLE_USDC),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::VoucherAlreadyUsed { nonce: 1 }
+    );
+
+    // Alice's voucher does not admit Bob, nor does one from another key
+    let voucher = sign_voucher(&key, &presale_addr, &alice, None, expiry, 2);
+    let err = buy(&mut setup.app, &bob, Some(voucher), coin(1_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidVoucherSignature {}
+    );
+    let rogue = SigningKey::from_bytes(&[9u8; 32].into()).unwrap();
+    let voucher = sign_voucher(&rogue, &presale_addr, &bob, None, expiry, 1);
+    let err = buy(&mut setup.app, &bob, Some(voucher), coin(1_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidVoucherSignature {}
+    );
+
+    // Expired vouchers are refused
+    let now = setup.app.block_info().time.seconds();
+    let voucher = sign_voucher(&key, &presale_addr, &bob, None, now, 1);
+    let err = buy(&mut setup.app, &bob, Some(voucher), coin(1_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::VoucherExpired {}
+    );
+
+    // A voucher keeps the cap the whitelist manager set for Bob
+    let bob_cap = Some(Uint128::new(600_000_000));
+    setup
+        .app
+        .execute_contract(
+            admin.clone(),
+            presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: vec![WhitelistAddition {
+                    address: bob.to_string(),
+                    cap: bob_cap,
+                    min: None,
+                }],
+            },
+            &[],
+        )
+        .unwrap();
+    let voucher = sign_voucher(&key, &presale_addr, &bob, None, expiry, 1);
+    buy(
+        &mut setup.app,
+        &bob,
+        Some(voucher),
+        coin(1_000_000_000, OSMO),
+    )
+    .unwrap();
+    let whitelisted: IsWhitelistedResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            presale_addr.clone(),
+            &QueryMsg::IsWhitelisted {
+                address: bob.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(whitelisted.cap, bob_cap);
+    let err = buy(&mut setup.app, &bob, None, coin(1_000_000_000, OSMO)).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UserCapExceeded {}
+    );
+
+    // A voucher does not lift an uncapped entry above the sale-wide limit
+    let mut uncapped = setup_with(|msg| {
+        msg.max_contribution_per_user = Uint128::new(1_000_000_000); // 1k USDC
+    });
+    uncapped
+        .app
+        .execute_contract(
+            uncapped.admin.clone(),
+            uncapped.presale_addr.clone(),
+            &ExecuteMsg::UpdateKycSigner {
+                pubkey: Some(Binary::from(key.verifying_key().to_sec1_bytes().as_ref())),
+            },
+            &[],
+        )
+        .unwrap();
+    uncapped
+        .app
+        .execute_contract(
+            uncapped.admin.clone(),
+            uncapped.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&uncapped.alice]),
+            },
+            &[],
+        )
+        .unwrap();
+    uncapped.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+    let voucher = sign_voucher(
+        &key,
+        &uncapped.presale_addr,
+        &uncapped.alice,
+        Some(Uint128::new(5_000_000_000)),
+        expiry,
+        1,
+    );
+    uncapped
+        .app
+        .execute_contract(
+            uncapped.alice.clone(),
+            uncapped.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: Some(voucher),
+            },
+            &[coin(1_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    let whitelisted: IsWhitelistedResponse = uncapped
+        .app
+        .wrap()
+        .query_wasm_smart(
+            uncapped.presale_addr.clone(),
+            &QueryMsg::IsWhitelisted {
+                address: uncapped.alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(whitelisted.cap, None);
+    let err = uncapped
+        .app
+        .execute_contract(
+            uncapped.alice.clone(),
+            uncapped.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UserCapExceeded {}
+    );
+}
+
+#[test]
+fn test_partial_fill_at_hard_cap() {
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000); // 1k USDC
+        msg.hard_cap = Uint128::new(10_000_000_000); // 10k USDC
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice, &setup.bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // 7k USDC from Alice and 1.1k USDC worth of OSMO from Bob leaves 1.9k of capacity
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(7_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, OSMO)],
+        )
+        .unwrap();
+
+    // Alice sends 5k USDC: 1.9k is accepted and 3.1k comes straight back
+    let usdc_before = setup
+        .app
+        .wrap()
+        .query_balance(setup.alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    let res = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(5_000_000_000, NOBLE_USDC)],
+        )
+        .unwrap();
+    let wasm = res.events.iter().find(|e| e.ty == "wasm").unwrap();
+    let attr = |key: &str| {
+        wasm.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(attr("accepted_amount"), "1900000000");
+    assert_eq!(attr("refunded_amount"), "3100000000");
+    assert_eq!(attr("usdc_value_added"), "1900000000");
+    let usdc_after = setup
+        .app
+        .wrap()
+        .query_balance(setup.alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    assert_eq!(usdc_before - usdc_after, Uint128::new(1_900_000_000));
+
+    // The sale closed itself at the hard cap
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.total_usdc_raised, Uint128::new(10_000_000_000));
+    assert_eq!(state.sale_status, SaleStatus::Succeeded);
+
+    let err = setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(1_000_000, NOBLE_USDC)],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::SaleNotActive {}, err.downcast().unwrap());
+}
+
+#[test]
+fn test_partial_fill_below_soft_cap() {
+    // With the soft cap at the hard cap, rounding in the partial fill leaves the sale short
+    let mut setup = setup_with(|msg| {
+        msg.soft_cap = Uint128::new(1_000_000_000);
+        msg.hard_cap = Uint128::new(1_000_000_000);
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    let res = setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::Buy {
+                whitelist_proof: None,
+                referrer: None,
+                kyc_voucher: None,
+            },
+            &[coin(2_000_000_000, OSMO)],
+        )
+        .unwrap();
+    assert_eq!(wasm_attribute(&res, "usdc_value_added"), "999999999");
+
+    // Still below the soft cap, so the sale stays open instead of succeeding
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.sale_status, SaleStatus::Active);
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::EndSale {},
+            &[],
+        )
+        .unwrap();
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.sale_status, SaleStatus::Failed);
+}
+
+#[test]
+fn test_fair_launch_pro_rata_settlement() {
+    let mut setup = setup_with(|msg| {
+        msg.sale_mode = SaleMode::FairLaunch;
+        msg.soft_cap = Uint128::new(1_000_000_000); // 1k USDC
+        msg.hard_cap = Uint128::new(10_000_000_000); // 10k USDC
+    });
+    setup
+        .app
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: setup.bob.to_string(),
+                amount: vec![coin(5_000_000_000, NOBLE_USDC)],
+            },
+        ))
+        .unwrap();
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::AddToWhitelist {
+                entries: uncapped_entries(&[&setup.alice, &setup.bob]),
+            },
+            &[],
+        )
+        .unwrap();
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(101);
+    });
+
+    // 15k + 5k USDC is accepted against a 10k hard cap: the sale is 2x oversubscribed
+    for (buyer, amount) in [
+        (setup.alice.clone(), 15_000_000_000),
+        (setup.bob.clone(), 5_000_000_000),
+    ] {
+        setup
+            .app
+            .execute_contract(
+                buyer,
+                setup.presale_addr.clone(),
+                &ExecuteMsg::Buy {
+                    whitelist_proof: None,
+                    referrer: None,
+                    kyc_voucher: None,
+                },
+                &[coin(amount, NOBLE_USDC)],
+            )
+            .unwrap();
+    }
+    let state: State = setup
+        .app
+        .wrap()
+        .query_wasm_smart(setup.presale_addr.clone(), &QueryMsg::State {})
+        .unwrap();
+    assert_eq!(state.total_usdc_raised, Uint128::new(20_000_000_000));
+    assert_eq!(state.sale_status, SaleStatus::Active);
+
+    // Projection while live: half of Alice's 1.5M EHO, and half her USDC back
+    let allocation: AllocationResponse = setup
+        .app
+        .wrap()
+        .query_wasm_smart(
+            setup.presale_addr.clone(),
+            &QueryMsg::EhoAllocationOf {
+                address: setup.alice.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        allocation,
+        AllocationResponse {
+            eho_allocation: Uint128::new(750_000_000_000),
+            referral_bonus: Uint128::zero(),
+            tier_bonus: Uint128::zero(),
+            refund: vec![Asset {
+                info: AssetInfo::Native {
+                    denom: NOBLE_USDC.to_string()
+                },
+                amount: Uint128::new(7_500_000_000),
+            }],
+        }
+    );
+
+    setup.app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+
+    // Alice settles: EHO and the unused USDC in one claim
+    let usdc_before = setup
+        .app
+        .wrap()
+        .query_balance(setup.alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    setup
+        .app
+        .execute_contract(
+            setup.alice.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &setup.alice),
+        Uint128::new(750_000_000_000)
+    );
+    let usdc_after = setup
+        .app
+        .wrap()
+        .query_balance(setup.alice.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    assert_eq!(usdc_after - usdc_before, Uint128::new(7_500_000_000));
+
+    // The admin only withdraws the 10k that was actually sold; Bob's refund stays behind
+    let admin_before = setup
+        .app
+        .wrap()
+        .query_balance(setup.admin.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    setup
+        .app
+        .execute_contract(
+            setup.admin.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::WithdrawFunds {},
+            &[],
+        )
+        .unwrap();
+    let admin_after = setup
+        .app
+        .wrap()
+        .query_balance(setup.admin.clone(), NOBLE_USDC)
+        .unwrap()
+        .amount;
+    assert_eq!(admin_after - admin_before, Uint128::new(10_000_000_000));
+
+    // Bob still gets his 2.5k USDC back alongside 250k EHO
+    setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&setup.app, &setup.eho_addr, &setup.bob),
+        Uint128::new(250_000_000_000)
+    );
+    assert_eq!(
+        setup
+            .app
+            .wrap()
+            .query_balance(setup.presale_addr.clone(), NOBLE_USDC)
+            .unwrap()
+            .amount,
+        Uint128::zero()
+    );
+
+    // FAIL: The refund is only paid once
+    let err = setup
+        .app
+        .execute_contract(
+            setup.bob.clone(),
+            setup.presale_addr.clone(),
+            &ExecuteMsg::ClaimTokens {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+}